@@ -0,0 +1,104 @@
+/// Which bit value survives each round of filtering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    /// Keep lines whose bit is the most common value in that position.
+    MostCommon,
+    /// Keep lines whose bit is the least common value in that position.
+    LeastCommon,
+}
+
+/// The order in which bit positions are considered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Start from the leftmost bit, like the puzzle does.
+    MsbFirst,
+    /// Start from the rightmost bit.
+    #[allow(dead_code)]
+    LsbFirst,
+}
+
+impl BitOrder {
+    /// Rearrange a line so that its bits are in the order they'll be considered.
+    /// Doing this twice gives back the original line.
+    pub fn arrange(self, line: &[bool]) -> Vec<bool> {
+        match self {
+            BitOrder::MsbFirst => line.to_vec(),
+            BitOrder::LsbFirst => line.iter().rev().copied().collect(),
+        }
+    }
+}
+
+/// Rules for whittling the diagnostic report down to a rating.
+/// The order bits are considered in belongs to the [`Trie`](crate::trie::Trie) being searched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitCriteria {
+    pub keep: Keep,
+    /// Which bit to keep when 0s and 1s are equally common.
+    pub on_tie: bool,
+    /// Stop filtering once this many lines (or fewer) remain.
+    pub stop_at: usize,
+}
+
+impl BitCriteria {
+    /// Which bit should be kept, given how many remaining lines have a 0 and a 1 in this position?
+    pub fn choose(&self, zeros: usize, ones: usize) -> bool {
+        if zeros == ones {
+            return self.on_tie;
+        }
+        match self.keep {
+            Keep::MostCommon => ones > zeros,
+            Keep::LeastCommon => ones < zeros,
+        }
+    }
+}
+
+/// The gases whose ratings the puzzle asks for. Each one is a preset [`BitCriteria`].
+#[derive(Clone, Copy, Debug)]
+pub enum Gas {
+    /// Keep lines with the most common bit, preferring 1 on ties.
+    Oxygen,
+    /// Keep lines with the least common bit, preferring 0 on ties.
+    CO2,
+}
+
+impl From<Gas> for BitCriteria {
+    fn from(gas: Gas) -> Self {
+        let (keep, on_tie) = match gas {
+            Gas::Oxygen => (Keep::MostCommon, true),
+            Gas::CO2 => (Keep::LeastCommon, false),
+        };
+        Self {
+            keep,
+            on_tie,
+            stop_at: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose() {
+        let oxygen = BitCriteria::from(Gas::Oxygen);
+        let co2 = BitCriteria::from(Gas::CO2);
+        let tests = [
+            // zeros, ones, oxygen's choice, CO2's choice
+            (5, 7, true, false),
+            (7, 5, false, true),
+            (6, 6, true, false),
+        ];
+        for (zeros, ones, expected_oxygen, expected_co2) in tests {
+            assert_eq!(oxygen.choose(zeros, ones), expected_oxygen);
+            assert_eq!(co2.choose(zeros, ones), expected_co2);
+        }
+    }
+
+    #[test]
+    fn test_arrange() {
+        let line = [true, true, false];
+        assert_eq!(BitOrder::MsbFirst.arrange(&line), vec![true, true, false]);
+        assert_eq!(BitOrder::LsbFirst.arrange(&line), vec![false, true, true]);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::binary_to_number;
+use std::fmt;
+
+/// Bit counts for one column of the diagnostic report.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ColumnReport {
+    pub ones: usize,
+    pub zeros: usize,
+}
+
+impl ColumnReport {
+    /// Were there as many 1s as 0s in this column?
+    pub fn is_tie(&self) -> bool {
+        self.ones == self.zeros
+    }
+
+    /// The most common bit. Ties count as 0, like the puzzle's answer expects.
+    pub fn gamma_bit(&self) -> bool {
+        self.ones > self.zeros
+    }
+
+    pub fn epsilon_bit(&self) -> bool {
+        !self.gamma_bit()
+    }
+}
+
+/// A column-by-column breakdown of how the gamma and epsilon rates were calculated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PowerReport {
+    pub columns: Vec<ColumnReport>,
+}
+
+/// Gamma can't be calculated unambiguously, because some columns had as many 1s as 0s.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AmbiguousGamma {
+    /// The tied columns, counting from the leftmost bit.
+    pub columns: Vec<usize>,
+}
+
+impl fmt::Display for AmbiguousGamma {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gamma is ambiguous, these columns are tied: {:?}",
+            self.columns
+        )
+    }
+}
+
+impl PowerReport {
+    pub fn new<'a, I>(lines: I) -> Self
+    where
+        I: Iterator<Item = &'a Vec<bool>>,
+    {
+        let mut columns: Vec<ColumnReport> = Vec::new();
+        for line in lines {
+            if columns.len() < line.len() {
+                columns.resize(line.len(), Default::default());
+            }
+            for (column, &bit) in columns.iter_mut().zip(line) {
+                if bit {
+                    column.ones += 1;
+                } else {
+                    column.zeros += 1;
+                }
+            }
+        }
+        Self { columns }
+    }
+
+    /// Which columns had as many 1s as 0s?
+    pub fn ties(&self) -> Vec<usize> {
+        (0..self.columns.len())
+            .filter(|&i| self.columns[i].is_tie())
+            .collect()
+    }
+
+    pub fn gamma_bits(&self) -> Vec<bool> {
+        self.columns.iter().map(ColumnReport::gamma_bit).collect()
+    }
+
+    /// Gamma rate multiplied by epsilon rate. Tied columns are treated as 0.
+    pub fn power_usage(&self) -> u32 {
+        let (gamma, epsilon) = binary_to_number(&self.gamma_bits());
+        gamma * epsilon
+    }
+
+    /// Like `power_usage`, but errors instead of guessing when a tie makes gamma ambiguous.
+    pub fn strict_power_usage(&self) -> Result<u32, AmbiguousGamma> {
+        let columns = self.ties();
+        if columns.is_empty() {
+            Ok(self.power_usage())
+        } else {
+            Err(AmbiguousGamma { columns })
+        }
+    }
+}
+
+impl fmt::Display for PowerReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "column  ones  zeros  gamma  epsilon  tie")?;
+        for (i, column) in self.columns.iter().enumerate() {
+            write!(
+                f,
+                "{:>6}  {:>4}  {:>5}  {:>5}  {:>7}",
+                i,
+                column.ones,
+                column.zeros,
+                column.gamma_bit() as u8,
+                column.epsilon_bit() as u8,
+            )?;
+            if column.is_tie() {
+                write!(f, "  yes")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read;
+
+    #[test]
+    fn test_example_report() {
+        let lines: Vec<Vec<bool>> = read(include_str!("example.txt")).collect();
+        let report = PowerReport::new(lines.iter());
+        assert_eq!(report.columns[0], ColumnReport { ones: 7, zeros: 5 });
+        assert_eq!(report.gamma_bits(), vec![true, false, true, true, false]);
+        assert!(report.ties().is_empty());
+        assert_eq!(report.strict_power_usage(), Ok(198));
+    }
+
+    #[test]
+    fn test_ties() {
+        let lines: Vec<Vec<bool>> = read("110\n011").collect();
+        let report = PowerReport::new(lines.iter());
+        assert_eq!(report.ties(), vec![0, 2]);
+        // Gamma is 010 and epsilon is 101 if ties count as 0.
+        assert_eq!(report.power_usage(), 2 * 5);
+        assert_eq!(
+            report.strict_power_usage(),
+            Err(AmbiguousGamma {
+                columns: vec![0, 2]
+            })
+        );
+        let table = report.to_string();
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.lines().nth(1).unwrap().ends_with("yes"));
+    }
+}
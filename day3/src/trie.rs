@@ -0,0 +1,184 @@
+use crate::criteria::{BitCriteria, BitOrder, Gas};
+
+/// A binary trie over every line of the diagnostic report.
+/// Each node remembers how many lines pass through it, so ratings and prefix queries
+/// are answered by walking down from the root instead of rescanning the report.
+pub struct Trie {
+    /// All nodes of the trie. The root is always at index 0.
+    nodes: Vec<Node>,
+    /// How many bits each line has.
+    width: usize,
+    /// The order bits were inserted in, i.e. which bit the root branches on. Searches consider
+    /// bits in this order too.
+    order: BitOrder,
+}
+
+#[derive(Default)]
+struct Node {
+    /// Index of the child for a 0 bit, and for a 1 bit.
+    children: [Option<usize>; 2],
+    /// How many lines have the prefix which leads to this node.
+    count: usize,
+}
+
+impl Trie {
+    pub fn new<'a, I>(lines: I, order: BitOrder) -> Self
+    where
+        I: IntoIterator<Item = &'a Vec<bool>>,
+    {
+        let mut trie = Self {
+            nodes: vec![Node::default()],
+            width: 0,
+            order,
+        };
+        for line in lines {
+            trie.insert(&order.arrange(line));
+        }
+        trie
+    }
+
+    fn insert(&mut self, line: &[bool]) {
+        self.width = self.width.max(line.len());
+        let mut curr = 0;
+        self.nodes[curr].count += 1;
+        for &bit in line {
+            curr = match self.nodes[curr].children[bit as usize] {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[curr].children[bit as usize] = Some(child);
+                    child
+                }
+            };
+            self.nodes[curr].count += 1;
+        }
+    }
+
+    /// How many lines start with the given bits?
+    /// The prefix is read in the trie's bit order, so for [`BitOrder::LsbFirst`] it's a suffix of each line.
+    #[allow(dead_code)]
+    pub fn count_with_prefix(&self, prefix: &[bool]) -> usize {
+        let mut curr = 0;
+        for &bit in prefix {
+            match self.nodes[curr].children[bit as usize] {
+                Some(child) => curr = child,
+                None => return 0,
+            }
+        }
+        self.nodes[curr].count
+    }
+
+    fn count(&self, node: Option<usize>) -> usize {
+        node.map(|i| self.nodes[i].count).unwrap_or_default()
+    }
+
+    /// Find the line which is left over after repeatedly applying the gas's bit criteria.
+    pub fn rating(&self, gas: Gas) -> Vec<bool> {
+        self.search(&gas.into()).swap_remove(0)
+    }
+
+    /// Find the lines which are left over after repeatedly applying the bit criteria, considering
+    /// bits in the trie's order.
+    /// Runs in O(width) plus the size of the output, because each step only has to compare the counts of
+    /// two children.
+    pub fn search(&self, criteria: &BitCriteria) -> Vec<Vec<bool>> {
+        let mut bits = Vec::with_capacity(self.width);
+        let mut curr = 0;
+        while self.nodes[curr].count > criteria.stop_at.max(1) {
+            let [zeros, ones] = self.nodes[curr].children;
+            let bit = match (self.count(zeros), self.count(ones)) {
+                (0, 0) => break,
+                // Once every remaining line agrees on this bit, there's only one way to go.
+                (0, _) => true,
+                (_, 0) => false,
+                (zeros, ones) => criteria.choose(zeros, ones),
+            };
+            bits.push(bit);
+            curr = self.nodes[curr].children[bit as usize].unwrap();
+        }
+        let mut remaining = Vec::new();
+        self.collect(curr, &mut bits, &mut remaining);
+        remaining
+    }
+
+    /// Add every line under the given node to `out`. `prefix` is the path from the root to the node.
+    fn collect(&self, node: usize, prefix: &mut Vec<bool>, out: &mut Vec<Vec<bool>>) {
+        let node = &self.nodes[node];
+        let children = node.children.map(|child| self.count(child));
+        // Lines which end at this node, rather than continuing into a child.
+        let ending_here = node.count - children.iter().sum::<usize>();
+        for _ in 0..ending_here {
+            out.push(self.order.arrange(prefix));
+        }
+        for (bit, child) in [false, true].into_iter().zip(node.children) {
+            if let Some(child) = child {
+                prefix.push(bit);
+                self.collect(child, prefix, out);
+                prefix.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::criteria::Keep;
+    use crate::read;
+
+    #[test]
+    fn test_count_with_prefix() {
+        let lines: Vec<Vec<bool>> = read(include_str!("example.txt")).collect();
+        let trie = Trie::new(&lines, BitOrder::MsbFirst);
+        assert_eq!(trie.count_with_prefix(&[]), 12);
+        assert_eq!(trie.count_with_prefix(&[true]), 7);
+        assert_eq!(trie.count_with_prefix(&[false]), 5);
+        assert_eq!(trie.count_with_prefix(&[true, false, true]), 3);
+        assert_eq!(trie.count_with_prefix(&[true, false, true, true, true]), 1);
+        assert_eq!(trie.count_with_prefix(&[false, false, false, false]), 0);
+    }
+
+    #[test]
+    fn test_rating_with_duplicate_lines() {
+        let lines: Vec<Vec<bool>> = read("101\n101\n010").collect();
+        let trie = Trie::new(&lines, BitOrder::MsbFirst);
+        assert_eq!(trie.rating(Gas::Oxygen), vec![true, false, true]);
+        assert_eq!(trie.rating(Gas::CO2), vec![false, true, false]);
+    }
+
+    #[test]
+    fn test_search_lsb_first() {
+        let lines: Vec<Vec<bool>> = read(include_str!("example.txt")).collect();
+        let trie = Trie::new(&lines, BitOrder::LsbFirst);
+        assert_eq!(
+            trie.search(&Gas::Oxygen.into()),
+            vec![read("11110").next().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_search_stop_at() {
+        let lines: Vec<Vec<bool>> = read(include_str!("example.txt")).collect();
+        let criteria = BitCriteria {
+            stop_at: 3,
+            ..Gas::Oxygen.into()
+        };
+        let trie = Trie::new(&lines, BitOrder::MsbFirst);
+        let expected: Vec<Vec<bool>> = read("10101\n10110\n10111").collect();
+        assert_eq!(trie.search(&criteria), expected);
+    }
+
+    #[test]
+    fn test_search_least_common_prefers_one() {
+        let lines: Vec<Vec<bool>> = read(include_str!("example.txt")).collect();
+        let criteria = BitCriteria {
+            keep: Keep::LeastCommon,
+            on_tie: true,
+            stop_at: 1,
+        };
+        let trie = Trie::new(&lines, BitOrder::MsbFirst);
+        // CO2 would break the tie on the second bit towards 0 and end up at 01010.
+        assert_eq!(trie.search(&criteria), vec![read("01111").next().unwrap()]);
+    }
+}
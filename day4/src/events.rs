@@ -0,0 +1,100 @@
+use crate::pattern::{Cells, Pattern};
+use crate::tracker::WinTracker;
+use crate::BingoGame;
+use std::collections::VecDeque;
+
+/// A board winning the game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Win {
+    /// Which round the board won in, starting from 1.
+    pub round: usize,
+    pub board: usize,
+    pub score: u64,
+    pub pattern: Pattern,
+    /// The cells (as row and column) which completed the pattern.
+    pub line: Cells,
+}
+
+/// Something that happened during a game of Bingo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A number was drawn at the start of a round.
+    Drawn { round: usize, number: u32 },
+    /// A board won. Boards which win in the same round are reported in board order.
+    Won(Win),
+    /// Every board has won, so the game is over.
+    Finished { round: usize },
+    /// There are no numbers left to draw, but these boards never won.
+    DrawsExhausted { round: usize, unwon: Vec<usize> },
+}
+
+/// Plays a game of Bingo one event at a time.
+/// The game always ends with either [`Event::Finished`] or [`Event::DrawsExhausted`].
+pub struct Events {
+    game: BingoGame,
+    tracker: WinTracker,
+    /// Events which happened this round, but haven't been yielded yet.
+    pending: VecDeque<Event>,
+    round: usize,
+    boards_won: usize,
+    over: bool,
+}
+
+impl Events {
+    pub fn new(game: BingoGame) -> Self {
+        Self {
+            tracker: WinTracker::new(&game.boards, &game.patterns),
+            game,
+            pending: VecDeque::new(),
+            round: 0,
+            boards_won: 0,
+            over: false,
+        }
+    }
+
+    /// Draw the next number and queue up everything that happened because of it.
+    fn play_round(&mut self) {
+        if self.boards_won == self.game.boards.len() {
+            self.pending
+                .push_back(Event::Finished { round: self.round });
+            self.over = true;
+            return;
+        }
+        let Some(number) = self.game.play_one_round() else {
+            self.pending.push_back(Event::DrawsExhausted {
+                round: self.round,
+                unwon: self.tracker.unwon(),
+            });
+            self.over = true;
+            return;
+        };
+        self.round += 1;
+        self.pending.push_back(Event::Drawn {
+            round: self.round,
+            number,
+        });
+        // Only boards containing the number can have won.
+        for (board, line) in self.tracker.draw(number) {
+            let line = self.tracker.line(line);
+            self.pending.push_back(Event::Won(Win {
+                round: self.round,
+                board,
+                score: self.tracker.unmarked_sum(board) * number as u64,
+                pattern: self.game.patterns[line.pattern].clone(),
+                line: line.cells.clone(),
+            }));
+            self.boards_won += 1;
+        }
+    }
+}
+
+impl Iterator for Events {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.over {
+            self.play_round();
+        }
+        self.pending.pop_front()
+    }
+}
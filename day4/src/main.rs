@@ -1,152 +1,308 @@
-use std::collections::HashSet;
-
-const BINGO_SIZE: usize = 5;
-
-/// A standard Bingo board with N columns and N rows.
-struct Board<T, const N: usize>([[T; N]; N]);
-
-impl<const N: usize> Board<u8, N> {
-    fn parse(lines: [&str; N]) -> Self {
-        Self(lines.map(|line| {
-            line.split_whitespace()
-                .map(|n| n.parse().unwrap())
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap()
-        }))
-    }
-
-    /// Is this board a winning Bingo?
-    fn is_winner(&self, drawn: &HashSet<u8>) -> bool {
-        let rows_and_columns: Vec<HashSet<_>> = (0..N)
-            .flat_map(|i| {
-                [
-                    (0..N).map(|j| self.0[j][i]).collect(), // Rows
-                    (0..N).map(|j| self.0[i][j]).collect(), // Columns
-                ]
-            })
-            .collect();
-        for row_or_column in rows_and_columns {
-            if row_or_column.is_subset(drawn) {
-                return true;
-            }
-        }
-        false
+mod events;
+mod montecarlo;
+mod parse;
+mod pattern;
+mod rng;
+mod tracker;
+mod validate;
+
+use events::{Event, Events, Win};
+use montecarlo::{Estimate, Odds};
+use pattern::Pattern;
+use validate::Problem;
+
+/// A Bingo board, stored as a list of rows. Every row has the same number of columns.
+#[derive(Clone)]
+struct Board(Vec<Vec<u32>>);
+
+impl Board {
+    fn rows(&self) -> usize {
+        self.0.len()
     }
 
-    fn unmarked<'a>(&'a self, drawn: &'a HashSet<u8>) -> impl Iterator<Item = u8> + 'a {
-        (0..N * N)
-            .map(|n| self.0[n / N][n % N])
-            .filter(|n| !drawn.contains(n))
+    fn columns(&self) -> usize {
+        self.0.first().map(Vec::len).unwrap_or_default()
     }
 }
 
+#[derive(Clone)]
 struct BingoGame {
     /// Remaining bingo numbers, in the reverse order they will be drawn.
-    draws: Vec<u8>,
-    /// Available boards for players
-    boards: Vec<Board<u8, BINGO_SIZE>>,
-    /// Numbers already drawn
-    seen: HashSet<u8>,
+    draws: Vec<u32>,
+    /// Available boards for players. They're all the same size.
+    boards: Vec<Board>,
+    /// Ways a board can win. Earlier patterns take priority when several are completed at once.
+    patterns: Vec<Pattern>,
 }
 
 impl BingoGame {
-    fn parse(s: &str) -> Self {
-        // Get the list of numbers
-        let mut lines = s.lines();
-        let mut draws: Vec<u8> = lines
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|num| num.parse().unwrap())
-            .collect();
-        draws.reverse();
-        lines.next();
-
-        // Get the boards by reading batches of lines.
-        let boards: BatchIterator<&str, _, BINGO_SIZE> = BatchIterator {
-            inner_iterator: lines.into_iter().filter(|l| !l.is_empty()),
-        };
-        Self {
-            draws,
-            boards: boards.into_iter().map(Board::parse).collect(),
-            seen: Default::default(),
-        }
+    /// Draw the next number, if there are any left.
+    fn play_one_round(&mut self) -> Option<u32> {
+        self.draws.pop()
     }
 
-    fn play_one_round(&mut self) -> u8 {
-        let number_drawn = self.draws.pop().expect("Game is over");
-        self.seen.insert(number_drawn);
-        number_drawn
+    /// Play the game, one event at a time.
+    fn events(self) -> Events {
+        Events::new(self)
     }
 
-    fn play(mut self) -> (u32, u32) {
-        let mut first_winner = 0;
-        let mut winning_boards = HashSet::new();
-
-        loop {
-            // Each iteration is one round of the game.
-            let number_drawn = self.play_one_round() as u32;
-
-            // Check if any boards won.
-            for (board_num, board) in self.boards.iter().enumerate() {
-                if !winning_boards.contains(&board_num) && board.is_winner(&self.seen) {
-                    let score: u32 =
-                        board.unmarked(&self.seen).map(|n| n as u32).sum::<u32>() * number_drawn;
-                    if first_winner == 0 {
-                        first_winner = score
-                    }
-                    winning_boards.insert(board_num);
-                    if winning_boards.len() == self.boards.len() {
-                        return (first_winner, score);
-                    }
+    /// Play until every board has won. Returns every win, in the order they happened, or an error
+    /// if the draws run out first.
+    fn play(self) -> Result<Vec<Win>, Problem> {
+        let mut wins = Vec::with_capacity(self.boards.len());
+        for event in self.events() {
+            match event {
+                Event::Won(win) => wins.push(win),
+                Event::DrawsExhausted { unwon, .. } => {
+                    return Err(Problem::DrawsExhausted { unwon })
                 }
+                Event::Drawn { .. } | Event::Finished { .. } => {}
             }
         }
+        Ok(wins)
     }
 }
 
-/// An iterator adaptor. It takes an iterator, and returns N items at a time.
-struct BatchIterator<T, I: Iterator<Item = T>, const N: usize> {
-    inner_iterator: I,
-}
+fn main() {
+    let game = BingoGame::parse(include_str!("input.txt")).unwrap();
+    println!(
+        "Loaded {} boards and {} numbers",
+        game.boards.len(),
+        game.draws.len()
+    );
+    let validation = game.validate();
+    for warning in &validation.warnings {
+        println!("Warning: {}", warning);
+    }
+    for error in &validation.errors {
+        println!("Error: {}", error);
+    }
+    if !validation.is_ok() {
+        return;
+    }
+    let wins = game.clone().play().unwrap();
+    let (first_winner, last_winner) = match (wins.first(), wins.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            println!("There are no boards, so nobody wins");
+            return;
+        }
+    };
+    println!("Q1: {} (board {})", first_winner.score, first_winner.board);
+    println!("Q2: {} (board {})", last_winner.score, last_winner.board);
 
-impl<T, I: Iterator<Item = T>, const N: usize> Iterator for BatchIterator<T, I, N>
-where
-    T: Default,
-{
-    type Item = [T; N];
+    // How lucky were those boards? Replay the game with the draws shuffled.
+    let odds = montecarlo::estimate_odds(&game, 1000, 0);
+    let best = |key: fn(&Odds) -> Estimate| {
+        let odds = odds
+            .iter()
+            .max_by(|a, b| key(a).value.total_cmp(&key(b).value))
+            .unwrap();
+        (odds.board, key(odds))
+    };
+    for (label, (board, estimate)) in [("first", best(|o| o.first)), ("last", best(|o| o.last))] {
+        println!(
+            "Most likely to win {}: board {} ({:.1}%, 95% CI {:.1}-{:.1}%)",
+            label,
+            board,
+            estimate.value * 100.0,
+            estimate.low * 100.0,
+            estimate.high * 100.0,
+        );
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let line: Option<Vec<T>> = (0..N).map(|_| self.inner_iterator.next()).collect();
-        let line = line?.try_into().ok()?;
-        Some(line)
+    // Which board would win first with other ways of winning?
+    let corners_and_centre = Pattern::Mask(vec![
+        vec![true, false, false, false, true],
+        vec![false; 5],
+        vec![false, false, true, false, false],
+        vec![false; 5],
+        vec![true, false, false, false, true],
+    ]);
+    for (name, pattern) in [
+        ("diagonals", Pattern::Diagonals),
+        ("four corners", Pattern::FourCorners),
+        ("an X", Pattern::X),
+        ("a plus", Pattern::Plus),
+        ("blackout", Pattern::Blackout),
+        ("corners and centre", corners_and_centre),
+    ] {
+        let game = BingoGame {
+            patterns: vec![pattern],
+            ..game.clone()
+        };
+        let first_win = game.events().find_map(|event| match event {
+            Event::Won(win) => Some(win),
+            _ => None,
+        });
+        match first_win {
+            Some(win) => println!(
+                "Playing for {}: board {} wins first, scoring {}",
+                name, win.board, win.score
+            ),
+            None => println!("Playing for {}: no board ever wins", name),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
 
     #[test]
     fn test_parse() {
-        let game = BingoGame::parse(include_str!("example.txt"));
+        let game = BingoGame::parse(include_str!("example.txt")).unwrap();
         assert_eq!(game.boards.len(), 3);
         assert_eq!(game.draws.len(), 27);
-        let (first_winner, last_winner) = game.play();
-        assert_eq!(first_winner, 4512);
-        assert_eq!(last_winner, 1924);
+        let wins = game.play().unwrap();
+        assert_eq!(wins[0].score, 4512);
+        assert_eq!(wins[2].score, 1924);
     }
-}
 
-fn main() {
-    let game = BingoGame::parse(include_str!("input.txt"));
-    println!(
-        "Loaded {} boards and {} numbers",
-        game.boards.len(),
-        game.draws.len()
-    );
-    let (first_winner, last_winner) = game.play();
-    println!("Q1: {}", first_winner);
-    println!("Q2: {}", last_winner);
+    #[test]
+    fn test_winning_pattern() {
+        let mut game = BingoGame::parse(include_str!("example.txt")).unwrap();
+        game.patterns = vec![Pattern::Diagonals, Pattern::Rows, Pattern::Columns];
+        let wins = game.play().unwrap();
+        // Board 2 completes a diagonal before it completes the row which wins the normal game.
+        assert_eq!(wins[0].board, 2);
+        assert_eq!(wins[0].pattern, Pattern::Diagonals);
+        assert_eq!(wins[0].score, 494);
+        assert_eq!(wins[1].pattern, Pattern::Rows);
+        assert_eq!(wins[2].pattern, Pattern::Columns);
+    }
+
+    #[test]
+    fn test_events() {
+        let input = "1,2,4,3\n\n1 2\n3 4\n\n4 1\n5 6";
+        let events: Vec<_> = BingoGame::parse(input).unwrap().events().collect();
+        let won = |round, board, score, line| {
+            Event::Won(Win {
+                round,
+                board,
+                score,
+                pattern: Pattern::Rows,
+                line,
+            })
+        };
+        let expected = vec![
+            Event::Drawn {
+                round: 1,
+                number: 1,
+            },
+            Event::Drawn {
+                round: 2,
+                number: 2,
+            },
+            won(2, 0, (3 + 4) * 2, vec![(0, 0), (0, 1)]),
+            Event::Drawn {
+                round: 3,
+                number: 4,
+            },
+            won(3, 1, (5 + 6) * 4, vec![(0, 0), (0, 1)]),
+            Event::Finished { round: 3 },
+        ];
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn test_third_winner_and_losers() {
+        let game = BingoGame::parse(include_str!("example.txt")).unwrap();
+        let third = game
+            .events()
+            .filter_map(|event| match event {
+                Event::Won(win) => Some(win),
+                _ => None,
+            })
+            .nth(2)
+            .unwrap();
+        assert_eq!((third.board, third.score), (1, 1924));
+
+        // Nobody can win with only the first 5 numbers.
+        let mut game = BingoGame::parse(include_str!("example.txt")).unwrap();
+        game.draws.drain(..game.draws.len() - 5);
+        let last = game.events().last().unwrap();
+        assert_eq!(
+            last,
+            Event::DrawsExhausted {
+                round: 5,
+                unwon: vec![0, 1, 2]
+            }
+        );
+    }
+
+    #[test]
+    fn test_play_runs_out_of_draws() {
+        let game = BingoGame::parse("1,2\n\n1 2\n3 4\n\n3 4\n5 6").unwrap();
+        assert_eq!(game.play(), Err(Problem::DrawsExhausted { unwon: vec![1] }));
+    }
+
+    #[test]
+    fn test_rectangular_boards() {
+        let input = "1,2,3,7,8,9\n\n1 2 3\n4 5 6\n\n7 8 9\n10 11 12";
+        let wins = BingoGame::parse(input).unwrap().play().unwrap();
+        let scores: Vec<_> = wins.iter().map(|win| (win.board, win.score)).collect();
+        assert_eq!(scores, vec![(0, (4 + 5 + 6) * 3), (1, (10 + 11 + 12) * 9)]);
+    }
+
+    #[test]
+    fn test_matches_rescanning() {
+        let configs = [
+            Pattern::standard(),
+            vec![Pattern::Diagonals, Pattern::FourCorners],
+            vec![Pattern::X, Pattern::Plus],
+            vec![Pattern::Blackout],
+            vec![Pattern::Mask(vec![vec![true; 2]; 2])],
+            // The empty mask can never win, so only rows count.
+            vec![Pattern::Mask(vec![vec![false; 5]; 5]), Pattern::Rows],
+        ];
+        for input in [include_str!("example.txt"), include_str!("input.txt")] {
+            for patterns in &configs {
+                let mut game = BingoGame::parse(input).unwrap();
+                game.patterns = patterns.clone();
+                let expected = play_by_rescanning(&mut BingoGame::parse(input).unwrap(), patterns);
+                let actual: Vec<_> = game
+                    .play()
+                    .unwrap()
+                    .into_iter()
+                    .map(|win| (win.board, win.score))
+                    .collect();
+                assert_eq!(actual, expected, "{:?}", patterns);
+            }
+        }
+    }
+
+    /// Plays the game by checking every winning line of every board after each draw.
+    /// Returns each board's number and score, in the order they won.
+    fn play_by_rescanning(game: &mut BingoGame, patterns: &[Pattern]) -> Vec<(usize, u64)> {
+        let lines: Vec<_> = patterns
+            .iter()
+            .flat_map(|pattern| pattern.lines(game.boards[0].rows(), game.boards[0].columns()))
+            .collect();
+        let mut seen = HashSet::new();
+        let mut wins: Vec<(usize, u64)> = Vec::new();
+        loop {
+            let number_drawn = game.play_one_round().unwrap();
+            seen.insert(number_drawn);
+            for (board_num, board) in game.boards.iter().enumerate() {
+                let won = lines
+                    .iter()
+                    .any(|line| line.iter().all(|&(i, j)| seen.contains(&board.0[i][j])));
+                if won && !wins.iter().any(|&(b, _)| b == board_num) {
+                    let unmarked: u64 = board
+                        .0
+                        .iter()
+                        .flatten()
+                        .filter(|n| !seen.contains(n))
+                        .map(|&n| n as u64)
+                        .sum();
+                    wins.push((board_num, unmarked * number_drawn as u64));
+                    if wins.len() == game.boards.len() {
+                        return wins;
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,163 @@
+use crate::events::Event;
+use crate::rng::Rng;
+use crate::BingoGame;
+
+/// z-score for a 95% confidence interval.
+const Z: f64 = 1.96;
+
+/// An estimated quantity, with a 95% confidence interval around it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Estimate {
+    /// Estimate a probability from `hits` successes out of `trials`, using the Wilson score interval.
+    /// Unlike the usual normal approximation, it stays inside 0..=1 even when hits are rare.
+    fn proportion(hits: usize, trials: usize) -> Self {
+        if trials == 0 {
+            return Self {
+                value: 0.0,
+                low: 0.0,
+                high: 1.0,
+            };
+        }
+        let n = trials as f64;
+        let p = hits as f64 / n;
+        let denominator = 1.0 + Z * Z / n;
+        let centre = (p + Z * Z / (2.0 * n)) / denominator;
+        let half_width = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denominator;
+        Self {
+            value: p,
+            low: (centre - half_width).max(0.0),
+            high: (centre + half_width).min(1.0),
+        }
+    }
+
+    /// Estimate the mean of some samples. Returns None if there aren't any.
+    fn mean(samples: &[usize]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<usize>() as f64 / n;
+        let half_width = if samples.len() > 1 {
+            let variance = samples
+                .iter()
+                .map(|&x| (x as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1.0);
+            Z * (variance / n).sqrt()
+        } else {
+            0.0
+        };
+        Some(Self {
+            value: mean,
+            low: mean - half_width,
+            high: mean + half_width,
+        })
+    }
+}
+
+/// How one board fared over many games with shuffled draws.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Odds {
+    pub board: usize,
+    /// Chance of being the first board to win.
+    pub first: Estimate,
+    /// Chance of being the last board to win.
+    pub last: Estimate,
+    /// Which round the board wins in, on average. None if it never won.
+    pub round: Option<Estimate>,
+}
+
+/// Play the game `trials` times, shuffling the draws each time, and estimate each board's odds.
+/// When several boards win in the same round, the one with the lowest index counts as winning
+/// first or last, just like [`BingoGame::play`]. Games where some board never wins have no last
+/// winner.
+pub fn estimate_odds(game: &BingoGame, trials: usize, seed: u64) -> Vec<Odds> {
+    let mut rng = Rng::new(seed);
+    let boards = game.boards.len();
+    let mut firsts = vec![0; boards];
+    let mut lasts = vec![0; boards];
+    let mut rounds = vec![Vec::new(); boards];
+
+    for _ in 0..trials {
+        let mut game = game.clone();
+        rng.shuffle(&mut game.draws);
+        let mut first = None;
+        let mut last = None;
+        for event in game.events() {
+            match event {
+                Event::Won(win) => {
+                    first.get_or_insert(win.board);
+                    last = Some(win.board);
+                    rounds[win.board].push(win.round);
+                }
+                Event::DrawsExhausted { .. } => last = None,
+                Event::Drawn { .. } | Event::Finished { .. } => {}
+            }
+        }
+        if let Some(board) = first {
+            firsts[board] += 1;
+        }
+        if let Some(board) = last {
+            lasts[board] += 1;
+        }
+    }
+
+    (0..boards)
+        .map(|board| Odds {
+            board,
+            first: Estimate::proportion(firsts[board], trials),
+            last: Estimate::proportion(lasts[board], trials),
+            round: Estimate::mean(&rounds[board]),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let game = BingoGame::parse(include_str!("example.txt")).unwrap();
+        assert_eq!(estimate_odds(&game, 50, 1), estimate_odds(&game, 50, 1));
+    }
+
+    #[test]
+    fn test_probabilities() {
+        let game = BingoGame::parse(include_str!("example.txt")).unwrap();
+        let trials = 500;
+        let odds = estimate_odds(&game, trials, 2021);
+        let total_first: f64 = odds.iter().map(|o| o.first.value).sum();
+        let total_last: f64 = odds.iter().map(|o| o.last.value).sum();
+        assert!((total_first - 1.0).abs() < 1e-9);
+        assert!((total_last - 1.0).abs() < 1e-9);
+        for o in odds {
+            assert!(o.first.low <= o.first.value && o.first.value <= o.first.high);
+            let round = o.round.unwrap();
+            assert!(round.low <= round.value && round.value <= round.high);
+            // Nobody can win before 5 numbers have been drawn.
+            assert!(round.value >= 5.0);
+        }
+    }
+
+    #[test]
+    fn test_single_board() {
+        let game = BingoGame::parse("1,2,3,4\n\n1 2\n3 4").unwrap();
+        let odds = estimate_odds(&game, 20, 0);
+        assert_eq!(odds[0].first.value, 1.0);
+        assert_eq!(odds[0].last.value, 1.0);
+    }
+
+    #[test]
+    fn test_wilson_interval() {
+        let estimate = Estimate::proportion(0, 100);
+        assert_eq!(estimate.low, 0.0);
+        assert!(estimate.high > 0.0 && estimate.high < 0.05);
+    }
+}
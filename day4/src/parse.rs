@@ -0,0 +1,152 @@
+use crate::pattern::Pattern;
+use crate::{BingoGame, Board};
+use std::fmt;
+
+/// Problems with the puzzle input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input didn't even have a line of numbers to draw.
+    MissingDraws,
+    /// Something which should have been a number wasn't.
+    BadNumber(String),
+    /// Some row of this board has a different number of columns to the first row.
+    RaggedBoard { board: usize },
+    /// This board is a different size to the first board. Sizes are (rows, columns).
+    InconsistentSize {
+        board: usize,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingDraws => write!(f, "no numbers to draw"),
+            ParseError::BadNumber(s) => write!(f, "{:?} is not a number", s),
+            ParseError::RaggedBoard { board } => {
+                write!(f, "board {} has rows of different lengths", board)
+            }
+            ParseError::InconsistentSize {
+                board,
+                expected,
+                found,
+            } => write!(
+                f,
+                "board {} is {}x{}, but the first board is {}x{}",
+                board, found.0, found.1, expected.0, expected.1
+            ),
+        }
+    }
+}
+
+fn parse_number(s: &str) -> Result<u32, ParseError> {
+    s.parse().map_err(|_| ParseError::BadNumber(s.to_owned()))
+}
+
+impl Board {
+    /// Parse a board from its rows of whitespace-separated numbers.
+    /// `index` is only used to say which board was wrong, if there's an error.
+    fn parse(lines: &[&str], index: usize) -> Result<Self, ParseError> {
+        let rows = lines
+            .iter()
+            .map(|line| line.split_whitespace().map(parse_number).collect())
+            .collect::<Result<Vec<Vec<_>>, _>>()?;
+        let board = Self(rows);
+        if board.0.iter().any(|row| row.len() != board.columns()) {
+            return Err(ParseError::RaggedBoard { board: index });
+        }
+        Ok(board)
+    }
+}
+
+impl BingoGame {
+    /// Parse the puzzle input: a line of numbers to draw, then boards separated by blank lines.
+    /// Boards can be any size, as long as they're all the same size.
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        // Get the list of numbers
+        let mut lines = s.lines();
+        let mut draws = lines
+            .next()
+            .filter(|line| !line.trim().is_empty())
+            .ok_or(ParseError::MissingDraws)?
+            .split(',')
+            .map(|num| parse_number(num.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        draws.reverse();
+
+        // Get the boards by reading blocks of lines between blank lines.
+        let mut boards: Vec<Board> = Vec::new();
+        let mut block = Vec::new();
+        for line in lines.chain(std::iter::once("")) {
+            if !line.trim().is_empty() {
+                block.push(line);
+                continue;
+            }
+            if block.is_empty() {
+                continue;
+            }
+            let board = Board::parse(&block, boards.len())?;
+            block.clear();
+            if let Some(first) = boards.first() {
+                let expected = (first.rows(), first.columns());
+                let found = (board.rows(), board.columns());
+                if expected != found {
+                    return Err(ParseError::InconsistentSize {
+                        board: boards.len(),
+                        expected,
+                        found,
+                    });
+                }
+            }
+            boards.push(board);
+        }
+        Ok(Self {
+            draws,
+            boards,
+            patterns: Pattern::standard(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_example() {
+        let game = BingoGame::parse(include_str!("example.txt")).unwrap();
+        assert_eq!(game.boards.len(), 3);
+        assert_eq!(game.draws.len(), 27);
+        assert_eq!(game.boards[0].0[0], vec![22, 13, 17, 11, 0]);
+    }
+
+    #[test]
+    fn test_parse_rectangle() {
+        let input = "1,2,3000000000\n\n1 2 3\n4 5 6\n\n7 8 9\n10 11 3000000000\n";
+        let game = BingoGame::parse(input).unwrap();
+        assert_eq!(game.boards.len(), 2);
+        assert_eq!((game.boards[1].rows(), game.boards[1].columns()), (2, 3));
+        assert_eq!(game.boards[1].0[1][2], 3_000_000_000);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tests = [
+            ("", ParseError::MissingDraws),
+            ("1,x\n\n1 2\n3 4", ParseError::BadNumber("x".to_owned())),
+            ("1\n\n1 2\n3", ParseError::RaggedBoard { board: 0 }),
+            (
+                "1\n\n1 2\n3 4\n\n1 2 3\n4 5 6",
+                ParseError::InconsistentSize {
+                    board: 1,
+                    expected: (2, 2),
+                    found: (2, 3),
+                },
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(BingoGame::parse(input).err(), Some(expected));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+/// A way to win at Bingo. A board wins with a pattern once every cell of any one of the pattern's
+/// lines has been marked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Any full row.
+    Rows,
+    /// Any full column.
+    Columns,
+    /// Either full diagonal.
+    Diagonals,
+    /// All four corners.
+    FourCorners,
+    /// Both diagonals at once.
+    X,
+    /// The middle row and middle column at once.
+    Plus,
+    /// Every cell on the board.
+    Blackout,
+    /// A user-defined set of cells, given as rows of the board. `true` cells must be marked.
+    /// Cells which fall outside the board are ignored, and a mask with no cells on the board can
+    /// never win.
+    Mask(Vec<Vec<bool>>),
+}
+
+/// The cells (as row and column) which must all be marked to win.
+pub type Cells = Vec<(usize, usize)>;
+
+impl Pattern {
+    /// The patterns of a standard game of Bingo.
+    pub fn standard() -> Vec<Self> {
+        vec![Pattern::Rows, Pattern::Columns]
+    }
+
+    /// Every set of cells which would win with this pattern, on a board of the given size.
+    /// Diagonals only exist on square boards, so `Diagonals` and `X` can't win on other boards.
+    pub fn lines(&self, rows: usize, columns: usize) -> Vec<Cells> {
+        if rows == 0 || columns == 0 {
+            return Vec::new();
+        }
+        let square = rows == columns;
+        let diagonal = || (0..rows).map(|i| (i, i)).collect::<Cells>();
+        let antidiagonal = || (0..rows).map(|i| (i, columns - 1 - i)).collect::<Cells>();
+        let (last_row, last_column) = (rows - 1, columns - 1);
+        let mut lines = match self {
+            Pattern::Rows => (0..rows)
+                .map(|i| (0..columns).map(|j| (i, j)).collect())
+                .collect(),
+            Pattern::Columns => (0..columns)
+                .map(|j| (0..rows).map(|i| (i, j)).collect())
+                .collect(),
+            Pattern::Diagonals if square => vec![diagonal(), antidiagonal()],
+            Pattern::X if square => vec![[diagonal(), antidiagonal()].concat()],
+            Pattern::Diagonals | Pattern::X => Vec::new(),
+            Pattern::FourCorners => vec![vec![
+                (0, 0),
+                (0, last_column),
+                (last_row, 0),
+                (last_row, last_column),
+            ]],
+            Pattern::Plus => {
+                let row = (0..columns).map(|j| (rows / 2, j));
+                let column = (0..rows).map(|i| (i, columns / 2));
+                vec![row.chain(column).collect()]
+            }
+            Pattern::Blackout => vec![(0..rows)
+                .flat_map(|i| (0..columns).map(move |j| (i, j)))
+                .collect()],
+            Pattern::Mask(mask) => vec![mask
+                .iter()
+                .enumerate()
+                .flat_map(|(i, row)| {
+                    row.iter()
+                        .enumerate()
+                        .filter(|(_, &marked)| marked)
+                        .map(move |(j, _)| (i, j))
+                })
+                .filter(|&(i, j)| i < rows && j < columns)
+                .collect()],
+        };
+        // Patterns like X and Plus overlap themselves, but each cell only needs to be marked once.
+        for line in &mut lines {
+            line.sort_unstable();
+            line.dedup();
+        }
+        // A line with no cells would be complete before anything is drawn.
+        lines.retain(|line| !line.is_empty());
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let tests = [
+            (Pattern::Rows, 5, 5),
+            (Pattern::Columns, 5, 5),
+            (Pattern::Diagonals, 2, 5),
+            (Pattern::FourCorners, 1, 4),
+            (Pattern::X, 1, 9),
+            (Pattern::Plus, 1, 9),
+            (Pattern::Blackout, 1, 25),
+        ];
+        for (pattern, expected_lines, expected_cells) in tests {
+            let lines = pattern.lines(5, 5);
+            assert_eq!(lines.len(), expected_lines, "{:?}", pattern);
+            for line in lines {
+                assert_eq!(line.len(), expected_cells, "{:?}", pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lines_on_rectangle() {
+        assert_eq!(Pattern::Rows.lines(2, 3)[1], vec![(1, 0), (1, 1), (1, 2)]);
+        assert_eq!(Pattern::Columns.lines(2, 3)[2], vec![(0, 2), (1, 2)]);
+        assert!(Pattern::Diagonals.lines(2, 3).is_empty());
+        assert_eq!(
+            Pattern::FourCorners.lines(2, 3),
+            vec![vec![(0, 0), (0, 2), (1, 0), (1, 2)]]
+        );
+    }
+
+    #[test]
+    fn test_mask() {
+        let mask = Pattern::Mask(vec![vec![true, false], vec![false, true]]);
+        assert_eq!(mask.lines(2, 2), vec![vec![(0, 0), (1, 1)]]);
+        assert!(Pattern::Mask(vec![vec![false; 2]; 2])
+            .lines(2, 2)
+            .is_empty());
+        // Only the cell at (0, 2) is marked, and it's off the board.
+        let off_board = Pattern::Mask(vec![vec![false, false, true]]);
+        assert!(off_board.lines(2, 2).is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::pattern::{Cells, Pattern};
+use crate::Board;
+use std::collections::{HashMap, HashSet};
+
+/// Tracks how close every board is to winning.
+/// Drawing a number only touches the cells which hold that number, so each draw costs O(1) per
+/// affected cell and winning line, no matter how many boards are in play.
+pub struct WinTracker {
+    /// Every cell which holds a given number, as (board, row, column).
+    cells: HashMap<u32, Vec<(usize, usize, usize)>>,
+    /// How many columns each board has.
+    columns: usize,
+    /// Every winning line. They're ordered by pattern, so a lower index means an earlier pattern.
+    lines: Vec<WinningLine>,
+    /// Which winning lines pass through each cell, in row-major order.
+    lines_through: Vec<Vec<usize>>,
+    /// How many cells have been marked in each winning line of each board.
+    hits: Vec<Vec<usize>>,
+    /// Sum of the unmarked numbers on each board.
+    unmarked_sum: Vec<u64>,
+    /// Which boards have already won.
+    won: Vec<bool>,
+    /// Numbers already drawn. Drawing one of these again doesn't mark anything.
+    drawn: HashSet<u32>,
+}
+
+/// One set of cells which wins the game once they're all marked.
+pub struct WinningLine {
+    /// Index of the pattern this line belongs to.
+    pub pattern: usize,
+    pub cells: Cells,
+}
+
+impl WinTracker {
+    /// Start tracking a game. Every board must be the same size.
+    pub fn new(boards: &[Board], patterns: &[Pattern]) -> Self {
+        let (rows, columns) = boards
+            .first()
+            .map(|board| (board.rows(), board.columns()))
+            .unwrap_or_default();
+        let mut cells: HashMap<_, Vec<_>> = HashMap::new();
+        for (board_num, board) in boards.iter().enumerate() {
+            for (row, numbers) in board.0.iter().enumerate() {
+                for (column, &number) in numbers.iter().enumerate() {
+                    cells
+                        .entry(number)
+                        .or_default()
+                        .push((board_num, row, column));
+                }
+            }
+        }
+        let mut lines = Vec::new();
+        let mut lines_through = vec![Vec::new(); rows * columns];
+        for (pattern_num, pattern) in patterns.iter().enumerate() {
+            for line in pattern.lines(rows, columns) {
+                for &(row, column) in &line {
+                    lines_through[row * columns + column].push(lines.len());
+                }
+                lines.push(WinningLine {
+                    pattern: pattern_num,
+                    cells: line,
+                });
+            }
+        }
+        Self {
+            cells,
+            columns,
+            hits: vec![vec![0; lines.len()]; boards.len()],
+            lines,
+            lines_through,
+            unmarked_sum: boards
+                .iter()
+                .map(|board| board.0.iter().flatten().map(|&n| n as u64).sum())
+                .collect(),
+            won: vec![false; boards.len()],
+            drawn: HashSet::new(),
+        }
+    }
+
+    /// Mark the number on every board. Returns the boards which won because of it, in board order,
+    /// along with the index of the line they won with. If a board completed several lines at once,
+    /// the earliest one is reported.
+    pub fn draw(&mut self, number: u32) -> Vec<(usize, usize)> {
+        let mut winners: HashMap<usize, usize> = HashMap::new();
+        if !self.drawn.insert(number) {
+            return Vec::new();
+        }
+        for &(board, row, column) in self.cells.get(&number).into_iter().flatten() {
+            self.unmarked_sum[board] -= number as u64;
+            for &line in &self.lines_through[row * self.columns + column] {
+                self.hits[board][line] += 1;
+                if self.hits[board][line] == self.lines[line].cells.len() && !self.won[board] {
+                    let best = winners.entry(board).or_insert(line);
+                    *best = line.min(*best);
+                }
+            }
+        }
+        let mut winners: Vec<_> = winners.into_iter().collect();
+        for &(board, _) in &winners {
+            self.won[board] = true;
+        }
+        winners.sort_unstable();
+        winners
+    }
+
+    pub fn line(&self, line: usize) -> &WinningLine {
+        &self.lines[line]
+    }
+
+    /// Which boards haven't won yet?
+    pub fn unwon(&self) -> Vec<usize> {
+        (0..self.won.len())
+            .filter(|&board| !self.won[board])
+            .collect()
+    }
+
+    /// Sum of the numbers on the board which haven't been drawn yet.
+    pub fn unmarked_sum(&self, board: usize) -> u64 {
+        self.unmarked_sum[board]
+    }
+}
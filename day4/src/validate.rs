@@ -0,0 +1,152 @@
+use crate::tracker::WinTracker;
+use crate::BingoGame;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Something suspicious about a game of Bingo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The number appears more than once on the board, so drawing it marks several cells.
+    DuplicateNumber { board: usize, number: u32 },
+    /// The number appears more than once in the draws. Drawing it again doesn't do anything.
+    DuplicateDraw { number: u32, times: usize },
+    /// The draws run out before every board has won, so the game can't finish. Every number is
+    /// drawn by then, so these boards can never win, whatever order the numbers come in.
+    DrawsExhausted { unwon: Vec<usize> },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::DuplicateNumber { board, number } => {
+                write!(f, "board {} has {} more than once", board, number)
+            }
+            Problem::DuplicateDraw { number, times } => {
+                write!(f, "{} is drawn {} times", number, times)
+            }
+            Problem::DrawsExhausted { unwon } => {
+                write!(f, "draws run out before boards {:?} win", unwon)
+            }
+        }
+    }
+}
+
+/// Everything suspicious about a game. Errors mean the game can't be played properly, warnings
+/// mean it can, but it's probably not what was intended.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Validation {
+    pub errors: Vec<Problem>,
+    pub warnings: Vec<Problem>,
+}
+
+impl Validation {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl BingoGame {
+    /// Check the boards and draws for problems, without playing the game.
+    pub fn validate(&self) -> Validation {
+        let mut validation = Validation::default();
+
+        for (board_num, board) in self.boards.iter().enumerate() {
+            for (number, times) in frequencies(board.0.iter().flatten()) {
+                if times > 1 {
+                    validation.warnings.push(Problem::DuplicateNumber {
+                        board: board_num,
+                        number,
+                    });
+                }
+            }
+        }
+
+        for (number, times) in frequencies(self.draws.iter().rev()) {
+            if times > 1 {
+                validation
+                    .warnings
+                    .push(Problem::DuplicateDraw { number, times });
+            }
+        }
+
+        // Drawing every number shows which boards could ever win, whatever the order.
+        let mut tracker = WinTracker::new(&self.boards, &self.patterns);
+        for &number in &self.draws {
+            tracker.draw(number);
+        }
+        let unwon = tracker.unwon();
+        if !unwon.is_empty() {
+            validation.errors.push(Problem::DrawsExhausted { unwon });
+        }
+
+        validation
+    }
+}
+
+/// How many times each item appears, in order of first appearance.
+fn frequencies<'a>(items: impl Iterator<Item = &'a u32>) -> Vec<(u32, usize)> {
+    let mut order = Vec::new();
+    let mut counts = HashMap::new();
+    for &item in items {
+        let count = counts.entry(item).or_insert(0);
+        if *count == 0 {
+            order.push(item);
+        }
+        *count += 1;
+    }
+    order
+        .into_iter()
+        .map(|item| (item, counts[&item]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_example() {
+        let game = BingoGame::parse(include_str!("example.txt")).unwrap();
+        assert_eq!(game.validate(), Validation::default());
+    }
+
+    #[test]
+    fn test_problems() {
+        let input = "1,2,2,3\n\n1 2\n3 3\n\n4 5\n6 7";
+        let validation = BingoGame::parse(input).unwrap().validate();
+        assert!(!validation.is_ok());
+        assert_eq!(
+            validation.errors,
+            vec![Problem::DrawsExhausted { unwon: vec![1] }]
+        );
+        assert_eq!(
+            validation.warnings,
+            vec![
+                Problem::DuplicateNumber {
+                    board: 0,
+                    number: 3
+                },
+                Problem::DuplicateDraw {
+                    number: 2,
+                    times: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_numbers_still_play() {
+        // Drawing 3 marks both 3s, so the bottom row wins.
+        let game = BingoGame::parse(
+            "3,9
+
+1 2
+3 3",
+        )
+        .unwrap();
+        let validation = game.validate();
+        assert!(validation.is_ok());
+        let wins = game.play().unwrap();
+        assert_eq!((wins[0].board, wins[0].score), (0, (1 + 2) * 3));
+    }
+}
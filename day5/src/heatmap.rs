@@ -0,0 +1,190 @@
+use crate::counter::Counter;
+use crate::index::Rect;
+use crate::lines::{Line, Point};
+
+/// Fields wider or taller than this are drawn as images, because they wouldn't fit in a terminal.
+const MAX_ASCII_SIZE: i64 = 100;
+/// Images are never wider or taller than this. Bigger fields are shrunk to fit.
+const MAX_IMAGE_SIZE: i64 = 2000;
+
+/// A picture of how many lines cover each point of the vent field.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Heatmap {
+    /// The puzzle's notation: `.` for no lines, otherwise the number of lines, or `#` for 10 or
+    /// more.
+    Ascii(String),
+    /// A binary PGM image, where brighter pixels are covered by more lines. If the field had to be
+    /// shrunk, each pixel shows the most covered point in its square block of the field.
+    Pgm(Vec<u8>),
+}
+
+impl Heatmap {
+    /// Draw the counts, cropped to the smallest rectangle containing every line.
+    /// Small fields are drawn as ASCII, large ones as images. Returns None if there are no lines.
+    pub fn new(counts: &Counter<Point>, lines: &[Line]) -> Option<Self> {
+        let rect = bounding_box(lines)?;
+        let (width, height) = size(rect);
+        if width <= MAX_ASCII_SIZE && height <= MAX_ASCII_SIZE {
+            Some(Self::ascii(counts, rect))
+        } else {
+            Some(Self::pgm(counts, rect))
+        }
+    }
+
+    pub fn ascii(counts: &Counter<Point>, rect: Rect) -> Self {
+        let mut s = String::new();
+        for y in rect.min.y..=rect.max.y {
+            for x in rect.min.x..=rect.max.x {
+                s.push(match counts.0.get(&Point { x, y }).copied().unwrap_or(0) {
+                    0 => '.',
+                    n @ 1..=9 => char::from_digit(n as u32, 10).unwrap(),
+                    _ => '#',
+                });
+            }
+            s.push('\n');
+        }
+        Self::Ascii(s)
+    }
+
+    pub fn pgm(counts: &Counter<Point>, rect: Rect) -> Self {
+        let (width, height) = size(rect);
+        // How many points wide each pixel is.
+        let scale = (width.max(height) + MAX_IMAGE_SIZE - 1) / MAX_IMAGE_SIZE;
+        let (columns, rows) = ((width + scale - 1) / scale, (height + scale - 1) / scale);
+        let mut pixels = vec![0; (columns * rows) as usize];
+        for (&p, &n) in &counts.0 {
+            if rect.contains(p) {
+                let column = (p.x as i64 - rect.min.x as i64) / scale;
+                let row = (p.y as i64 - rect.min.y as i64) / scale;
+                let pixel = &mut pixels[(row * columns + column) as usize];
+                *pixel = n.max(*pixel);
+            }
+        }
+        let max = pixels.iter().copied().max().unwrap_or(0);
+        let mut image = format!("P5\n{} {}\n255\n", columns, rows).into_bytes();
+        // Scale so the most covered point is white, and uncovered points are black.
+        image.extend(pixels.iter().map(|&n| {
+            if max == 0 {
+                0
+            } else {
+                (n as u32 * 255 / max as u32) as u8
+            }
+        }));
+        Self::Pgm(image)
+    }
+
+    /// The file extension this heatmap should be saved with.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Heatmap::Ascii(_) => "txt",
+            Heatmap::Pgm(_) => "pgm",
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Heatmap::Ascii(s) => s.as_bytes(),
+            Heatmap::Pgm(image) => image,
+        }
+    }
+}
+
+/// The smallest rectangle containing every line, or None if there are no lines.
+pub fn bounding_box(lines: &[Line]) -> Option<Rect> {
+    let points = lines.iter().flat_map(|l| [l.0, l.1]);
+    let min_x = points.clone().map(|p| p.x).min()?;
+    let min_y = points.clone().map(|p| p.y).min()?;
+    let max_x = points.clone().map(|p| p.x).max()?;
+    let max_y = points.map(|p| p.y).max()?;
+    Some(Rect {
+        min: Point { x: min_x, y: min_y },
+        max: Point { x: max_x, y: max_y },
+    })
+}
+
+/// How many points wide and tall the rectangle is.
+fn size(rect: Rect) -> (i64, i64) {
+    (
+        rect.max.x as i64 - rect.min.x as i64 + 1,
+        rect.max.y as i64 - rect.min.y as i64 + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lines::Rasterisation;
+    use crate::parse::parse_input;
+
+    #[test]
+    fn test_example_ascii() {
+        let lines = parse_input(include_str!("example.txt"));
+        let counts = crate::count_points(&lines, Rasterisation::Lattice);
+        let expected = "\
+1.1....11.
+.111...2..
+..2.1.111.
+...1.2.2..
+.112313211
+...1.2....
+..1...1...
+.1.....1..
+1.......1.
+222111....
+";
+        assert_eq!(
+            Heatmap::new(&counts, &lines),
+            Some(Heatmap::Ascii(expected.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_cropped() {
+        let lines = parse_input("-2,5 -> 0,5\n-1,4 -> -1,6");
+        let counts = crate::count_points(&lines, Rasterisation::Lattice);
+        let heatmap = Heatmap::new(&counts, &lines).unwrap();
+        assert_eq!(heatmap, Heatmap::Ascii(".1.\n121\n.1.\n".to_string()));
+        assert_eq!(heatmap.extension(), "txt");
+    }
+
+    #[test]
+    fn test_large_pgm() {
+        let lines = parse_input("0,0 -> 199,0\n0,0 -> 0,99\n0,0 -> 3,0");
+        let counts = crate::count_points(&lines, Rasterisation::Lattice);
+        let heatmap = Heatmap::new(&counts, &lines).unwrap();
+        assert_eq!(heatmap.extension(), "pgm");
+        let header = b"P5\n200 100\n255\n";
+        let image = heatmap.as_bytes();
+        assert!(image.starts_with(header));
+        let pixels = &image[header.len()..];
+        assert_eq!(pixels.len(), 200 * 100);
+        // (0,0) is covered by all three lines, (1,0) by two, (0,1) by one.
+        assert_eq!(pixels[0], 255);
+        assert_eq!(pixels[1], 170);
+        assert_eq!(pixels[200], 85);
+        assert_eq!(pixels[201], 0);
+    }
+
+    #[test]
+    fn test_huge_field_is_shrunk() {
+        let big = 1_000_000_000;
+        let lines = [Line(Point { x: -big, y: 0 }, Point { x: big, y: 0 })];
+        // Counting every point would take too long, so only count the ends.
+        let mut counts = Counter::default();
+        counts.add(lines[0].0);
+        counts.add(lines[0].0);
+        counts.add(lines[0].1);
+        let heatmap = Heatmap::new(&counts, &lines).unwrap();
+        let header = b"P5\n2000 1\n255\n";
+        let image = heatmap.as_bytes();
+        assert!(image.starts_with(header));
+        let pixels = &image[header.len()..];
+        assert_eq!(pixels.len(), 2000);
+        assert_eq!((pixels[0], pixels[1], pixels[1999]), (255, 0, 127));
+    }
+
+    #[test]
+    fn test_no_lines() {
+        assert_eq!(Heatmap::new(&Counter::default(), &[]), None);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::counter::Counter;
+use crate::lines::{Line, Point};
+use std::collections::{BTreeSet, HashMap};
+
+/// How many points wide and tall each bucket of the index is.
+const BUCKET_SIZE: i32 = 32;
+
+/// A rectangle of points, including its edges.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Rect {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Rect {
+    pub fn contains(&self, p: Point) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+}
+
+/// Two lines which share at least one point.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Intersection {
+    /// Indices of the two lines. `a` is always less than `b`.
+    pub a: usize,
+    pub b: usize,
+    /// The points both lines share.
+    pub segment: Line,
+}
+
+/// A spatial index over vent lines, for investigating specific parts of the vent field.
+/// The field is split into square buckets, and each bucket knows which lines have points in it.
+pub struct LineIndex<'a> {
+    lines: &'a [Line],
+    buckets: HashMap<(i32, i32), Vec<usize>>,
+}
+
+fn bucket(p: Point) -> (i32, i32) {
+    // Round towards negative infinity, so that buckets don't double in size around 0.
+    (p.x.div_euclid(BUCKET_SIZE), p.y.div_euclid(BUCKET_SIZE))
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(lines: &'a [Line]) -> Self {
+        let mut buckets: HashMap<_, Vec<_>> = HashMap::new();
+        for (i, line) in lines.iter().enumerate() {
+            let mut previous = None;
+            for p in line.points_in_line() {
+                // Consecutive points are usually in the same bucket, so only look it up on changes.
+                let b = bucket(p);
+                if previous != Some(b) {
+                    let entry = buckets.entry(b).or_default();
+                    if entry.last() != Some(&i) {
+                        entry.push(i);
+                    }
+                    previous = Some(b);
+                }
+            }
+        }
+        Self { lines, buckets }
+    }
+
+    /// Indices of every line with a point in the given buckets, sorted and without duplicates.
+    fn candidates(&self, min: (i32, i32), max: (i32, i32)) -> BTreeSet<usize> {
+        let area = (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1);
+        if area > self.buckets.len() as i64 {
+            // Big areas are mostly empty, so it's quicker to check every nonempty bucket.
+            let in_range =
+                |&(bx, by): &(i32, i32)| min.0 <= bx && bx <= max.0 && min.1 <= by && by <= max.1;
+            return self
+                .buckets
+                .iter()
+                .filter(|(b, _)| in_range(b))
+                .flat_map(|(_, lines)| lines)
+                .copied()
+                .collect();
+        }
+        (min.0..=max.0)
+            .flat_map(|bx| (min.1..=max.1).map(move |by| (bx, by)))
+            .filter_map(|b| self.buckets.get(&b))
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// Which lines pass through the point? Returns their indices, in order.
+    pub fn lines_through(&self, p: Point) -> Vec<usize> {
+        self.buckets
+            .get(&bucket(p))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|&i| self.lines[i].contains(p))
+            .collect()
+    }
+
+    /// Every point inside the rectangle which at least `k` lines pass through, in sorted order.
+    pub fn covered_at_least(&self, rect: Rect, k: u16) -> Vec<Point> {
+        let mut counter = Counter::default();
+        for i in self.candidates(bucket(rect.min), bucket(rect.max)) {
+            for p in self.lines[i].points_in_line().filter(|&p| rect.contains(p)) {
+                counter.add(p);
+            }
+        }
+        let mut points: Vec<_> = counter
+            .0
+            .into_iter()
+            .filter(|&(_, count)| count >= k)
+            .map(|(p, _)| p)
+            .collect();
+        points.sort();
+        points
+    }
+
+    /// Every pair of lines which share at least one point, sorted by the lines' indices.
+    pub fn intersections(&self) -> Vec<Intersection> {
+        let mut pairs = BTreeSet::new();
+        for lines in self.buckets.values() {
+            for (n, &a) in lines.iter().enumerate() {
+                for &b in &lines[n + 1..] {
+                    pairs.insert((a.min(b), a.max(b)));
+                }
+            }
+        }
+        pairs
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let segment = self.lines[a].intersection(&self.lines[b])?;
+                Some(Intersection { a, b, segment })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_input;
+
+    #[test]
+    fn test_lines_through() {
+        let lines = parse_input(include_str!("example.txt"));
+        let index = LineIndex::new(&lines);
+        // 0,9 -> 5,9 and 0,9 -> 2,9
+        assert_eq!(index.lines_through(Point { x: 1, y: 9 }), vec![0, 6]);
+        assert!(index.lines_through(Point { x: 9, y: 9 }).is_empty());
+    }
+
+    #[test]
+    fn test_covered_at_least() {
+        let lines = parse_input(include_str!("example.txt"));
+        let index = LineIndex::new(&lines);
+        let everything = Rect {
+            min: Point { x: 0, y: 0 },
+            max: Point { x: 9, y: 9 },
+        };
+        assert_eq!(index.covered_at_least(everything, 2).len(), 12);
+        assert_eq!(
+            index.covered_at_least(everything, 3),
+            vec![Point { x: 4, y: 4 }, Point { x: 6, y: 4 }]
+        );
+        let corner = Rect {
+            min: Point { x: 0, y: 0 },
+            max: Point { x: 2, y: 9 },
+        };
+        assert_eq!(
+            index.covered_at_least(corner, 2),
+            vec![
+                Point { x: 0, y: 9 },
+                Point { x: 1, y: 9 },
+                Point { x: 2, y: 2 },
+                Point { x: 2, y: 9 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_intersections() {
+        let lines = parse_input("0,0 -> 100,100\n100,0 -> 0,100\n50,0 -> 50,60\n0,3 -> 1,3");
+        let index = LineIndex::new(&lines);
+        let pair = |a, b, x, y| Intersection {
+            a,
+            b,
+            segment: Line(Point { x, y }, Point { x, y }),
+        };
+        assert_eq!(
+            index.intersections(),
+            vec![pair(0, 1, 50, 50), pair(0, 2, 50, 50), pair(1, 2, 50, 50)]
+        );
+    }
+
+    #[test]
+    fn test_negative_space() {
+        let lines =
+            parse_input("-40,-40 -> 40,40\n-40,40 -> 40,-40\n-50,0 -> 10,0\n-20,-5 -> -20,5");
+        let index = LineIndex::new(&lines);
+        assert_eq!(index.lines_through(Point { x: 0, y: 0 }), vec![0, 1, 2]);
+        let rect = Rect {
+            min: Point { x: -50, y: -50 },
+            max: Point { x: -1, y: 50 },
+        };
+        assert_eq!(
+            index.covered_at_least(rect, 2),
+            vec![Point { x: -20, y: 0 }]
+        );
+    }
+
+    #[test]
+    fn test_intersections_agree_with_points() {
+        let lines = parse_input(include_str!("input.txt"));
+        let index = LineIndex::new(&lines);
+        let mut counter = Counter::default();
+        for intersection in index.intersections() {
+            for p in intersection.segment.points_in_line() {
+                counter.add(p);
+            }
+        }
+        // Every point on two or more lines is in some intersection.
+        let (_, q2) = crate::solve(&lines, crate::Rasterisation::Lattice);
+        assert_eq!(counter.0.len(), q2);
+    }
+}
@@ -1,63 +1,229 @@
-use std::cmp::Ordering;
-
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
 pub struct Point {
-    pub x: u32,
-    pub y: u32,
+    pub x: i32,
+    pub y: i32,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
 pub struct Line(pub Point, pub Point);
 
+/// How to turn a line into the grid points it covers.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Rasterisation {
+    /// Only the points which lie exactly on the line.
+    Lattice,
+    /// Bresenham's algorithm: one point per step along the longer axis, choosing whichever grid
+    /// point is nearest to the line.
+    Bresenham,
+}
+
 impl Line {
-    /// Get all points along this line
+    /// Get all points which lie exactly on this line.
     pub fn points_in_line(&self) -> impl Iterator<Item = Point> {
+        let (dx, dy) = self.delta();
+        // Lattice points are evenly spaced along the line, and there are gcd(dx, dy) gaps between them.
+        let steps = gcd(dx.abs(), dy.abs());
+        let (step_x, step_y) = if steps == 0 {
+            (0, 0)
+        } else {
+            (dx / steps, dy / steps)
+        };
         PointsInLine {
-            started: false,
             curr: self.0,
-            end: self.1,
+            step_x,
+            step_y,
+            remaining: steps + 1,
         }
     }
+
+    /// Get the points which Bresenham's algorithm would draw for this line.
+    /// For horizontal, vertical and 45° lines, these are the same as `points_in_line`.
+    pub fn bresenham(&self) -> impl Iterator<Item = Point> {
+        let (dx, dy) = self.delta();
+        Bresenham {
+            curr: (self.0.x as i64, self.0.y as i64),
+            end: (self.1.x as i64, self.1.y as i64),
+            dx: dx.abs(),
+            dy: -dy.abs(),
+            step_x: dx.signum(),
+            step_y: dy.signum(),
+            error: dx.abs() - dy.abs(),
+            done: false,
+        }
+    }
+
+    /// Get the points this line covers, using the given rasterisation.
+    pub fn points(&self, rasterisation: Rasterisation) -> Box<dyn Iterator<Item = Point>> {
+        match rasterisation {
+            Rasterisation::Lattice => Box::new(self.points_in_line()),
+            Rasterisation::Bresenham => Box::new(self.bresenham()),
+        }
+    }
+
+    /// Does the point lie on this line?
+    pub fn contains(&self, p: Point) -> bool {
+        let (dx, dy) = self.delta();
+        let (px, py) = (p.x as i64 - self.0.x as i64, p.y as i64 - self.0.y as i64);
+        let collinear = cross((dx, dy), (px, py)) == 0;
+        let within = |a: i32, b: i32, v: i32| a.min(b) <= v && v <= a.max(b);
+        collinear && within(self.0.x, self.1.x, p.x) && within(self.0.y, self.1.y, p.y)
+    }
+
+    /// Which points lie on both lines? They're always a segment of both lines, which is returned
+    /// as a line from one end of the shared points to the other. A single shared point is a line
+    /// which starts and ends at that point.
+    pub fn intersection(&self, other: &Line) -> Option<Line> {
+        let (dx, dy) = self.delta();
+        let (ex, ey) = other.delta();
+        let (ox, oy) = (
+            other.0.x as i64 - self.0.x as i64,
+            other.0.y as i64 - self.0.y as i64,
+        );
+        // Deltas take up to 33 bits, so these products need more than an i64.
+        let denominator = cross((dx, dy), (ex, ey));
+        if denominator != 0 {
+            // The lines aren't parallel, so they cross at one point, a fraction t along this line.
+            let t = cross((ox, oy), (ex, ey));
+            let (x, y) = (
+                self.0.x as i128 * denominator + t * dx as i128,
+                self.0.y as i128 * denominator + t * dy as i128,
+            );
+            if x % denominator != 0 || y % denominator != 0 {
+                return None;
+            }
+            // Where the infinite lines cross might be off the grid entirely.
+            let p = Point {
+                x: i32::try_from(x / denominator).ok()?,
+                y: i32::try_from(y / denominator).ok()?,
+            };
+            return (self.contains(p) && other.contains(p)).then_some(Line(p, p));
+        }
+        if (dx, dy) == (0, 0) {
+            return other.contains(self.0).then_some(*self);
+        }
+        if (ex, ey) == (0, 0) {
+            return self.contains(other.0).then_some(*other);
+        }
+        if cross((ox, oy), (dx, dy)) != 0 {
+            // Parallel, but not on the same infinite line.
+            return None;
+        }
+        // Both lines are on the same infinite line. Number its lattice points so that this line
+        // goes from 0 to `steps`, and find which of those numbers the other line covers.
+        let steps = gcd(dx.abs(), dy.abs());
+        let (step_x, step_y) = (dx / steps, dy / steps);
+        let index = |p: Point| {
+            let (px, py) = (p.x as i64 - self.0.x as i64, p.y as i64 - self.0.y as i64);
+            if step_x != 0 {
+                px / step_x
+            } else {
+                py / step_y
+            }
+        };
+        let (a, b) = (index(other.0), index(other.1));
+        let (lo, hi) = (a.min(b).max(0), a.max(b).min(steps));
+        let at = |i: i64| Point {
+            x: (self.0.x as i64 + i * step_x) as i32,
+            y: (self.0.y as i64 + i * step_y) as i32,
+        };
+        (lo <= hi).then(|| Line(at(lo), at(hi)))
+    }
+
     pub fn is_straight(&self) -> bool {
         self.0.x == self.1.x || self.0.y == self.1.y
     }
+
+    /// How far the line goes along each axis.
+    fn delta(&self) -> (i64, i64) {
+        (
+            self.1.x as i64 - self.0.x as i64,
+            self.1.y as i64 - self.0.y as i64,
+        )
+    }
+}
+
+/// The cross product of two vectors, which is 0 when they're parallel.
+fn cross((ax, ay): (i64, i64), (bx, by): (i64, i64)) -> i128 {
+    ax as i128 * by as i128 - ay as i128 * bx as i128
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
-/// An iterator that yields all points along the line that created it.
+/// An iterator that yields all lattice points along the line that created it.
 struct PointsInLine {
     curr: Point,
-    end: Point,
-    started: bool,
+    step_x: i64,
+    step_y: i64,
+    /// How many points are left to yield, including `curr`.
+    remaining: i64,
 }
 
 impl Iterator for PointsInLine {
     type Item = Point;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.started {
-            // First point
-            self.started = true;
-            return Some(self.curr);
-        } else if self.curr == self.end {
-            // Last point
+        if self.remaining == 0 {
             return None;
         }
+        let point = self.curr;
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            // move `curr` one step along the line, to the next lattice point.
+            self.curr = Point {
+                x: (self.curr.x as i64 + self.step_x) as i32,
+                y: (self.curr.y as i64 + self.step_y) as i32,
+            };
+        }
+        Some(point)
+    }
+}
 
-        // move `curr` one step along the line, whichever direction
-        // it needs to go.
-        let x = &mut self.curr.x;
-        match (*x).cmp(&self.end.x) {
-            Ordering::Less => *x += 1,
-            Ordering::Greater => *x -= 1,
-            _ => {}
+/// An iterator that yields the points Bresenham's line algorithm draws.
+struct Bresenham {
+    curr: (i64, i64),
+    end: (i64, i64),
+    /// Absolute distance along x.
+    dx: i64,
+    /// Negative absolute distance along y.
+    dy: i64,
+    step_x: i64,
+    step_y: i64,
+    /// How far the drawn point is from the true line, scaled up to stay in integers.
+    error: i64,
+    done: bool,
+}
+
+impl Iterator for Bresenham {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
         }
-        let y = &mut self.curr.y;
-        match (*y).cmp(&self.end.y) {
-            Ordering::Less => *y += 1,
-            Ordering::Greater => *y -= 1,
-            _ => {}
-        };
-        Some(self.curr)
+        let (x, y) = self.curr;
+        if self.curr == self.end {
+            self.done = true;
+        } else {
+            let doubled_error = 2 * self.error;
+            if doubled_error >= self.dy {
+                self.error += self.dy;
+                self.curr.0 += self.step_x;
+            }
+            if doubled_error <= self.dx {
+                self.error += self.dx;
+                self.curr.1 += self.step_y;
+            }
+        }
+        Some(Point {
+            x: x as i32,
+            y: y as i32,
+        })
     }
 }
 
@@ -65,6 +231,14 @@ impl Iterator for PointsInLine {
 mod tests {
     use super::*;
 
+    fn line(x0: i32, y0: i32, x1: i32, y1: i32) -> Line {
+        Line(Point { x: x0, y: y0 }, Point { x: x1, y: y1 })
+    }
+
+    fn points(coords: &[(i32, i32)]) -> Vec<Point> {
+        coords.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
     #[test]
     fn test_points_in_line() {
         let l = Line(Point { x: 0, y: 2 }, Point { x: 0, y: 4 });
@@ -73,7 +247,7 @@ mod tests {
             Point { x: 0, y: 3 },
             Point { x: 0, y: 4 },
         ];
-        let actual: Vec<_> = l.points_in_line().into_iter().collect();
+        let actual: Vec<_> = l.points_in_line().collect();
         assert_eq!(expected, actual);
     }
 
@@ -81,7 +255,106 @@ mod tests {
     fn test_points_in_one_point_line() {
         let l = Line(Point { x: 0, y: 2 }, Point { x: 0, y: 2 });
         let expected = vec![Point { x: 0, y: 2 }];
-        let actual: Vec<_> = l.points_in_line().into_iter().collect();
+        let actual: Vec<_> = l.points_in_line().collect();
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_points_in_sloped_line() {
+        let tests = [
+            (line(0, 0, 6, 3), points(&[(0, 0), (2, 1), (4, 2), (6, 3)])),
+            (line(6, 3, 0, 0), points(&[(6, 3), (4, 2), (2, 1), (0, 0)])),
+            (line(1, 5, 4, 3), points(&[(1, 5), (4, 3)])),
+            (line(9, 7, 7, 9), points(&[(9, 7), (8, 8), (7, 9)])),
+            (
+                line(-4, 2, 2, -1),
+                points(&[(-4, 2), (-2, 1), (0, 0), (2, -1)]),
+            ),
+        ];
+        for (l, expected) in tests {
+            assert_eq!(l.points_in_line().collect::<Vec<_>>(), expected, "{:?}", l);
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        let l = line(0, 0, 6, 3);
+        assert!(l.contains(Point { x: 4, y: 2 }));
+        assert!(!l.contains(Point { x: 3, y: 1 }));
+        assert!(!l.contains(Point { x: 8, y: 4 }));
+    }
+
+    #[test]
+    fn test_intersection() {
+        let tests = [
+            // Crossing at a lattice point
+            (line(0, 0, 4, 4), line(0, 4, 4, 0), Some(line(2, 2, 2, 2))),
+            (
+                line(-3, -3, 3, 3),
+                line(-3, 3, 3, -3),
+                Some(line(0, 0, 0, 0)),
+            ),
+            // Crossing between lattice points
+            (line(0, 0, 3, 3), line(0, 3, 3, 0), None),
+            // Would cross if they were longer
+            (line(0, 0, 1, 1), line(0, 4, 4, 4), None),
+            // Overlapping on the same line, in opposite directions
+            (line(0, 0, 6, 3), line(8, 4, 2, 1), Some(line(2, 1, 6, 3))),
+            // Parallel
+            (line(0, 0, 5, 0), line(0, 1, 5, 1), None),
+            // Touching end to end
+            (line(0, 0, 5, 0), line(5, 0, 9, 0), Some(line(5, 0, 5, 0))),
+            // A single point on a line
+            (line(3, 3, 3, 3), line(0, 0, 6, 6), Some(line(3, 3, 3, 3))),
+        ];
+        for (a, b, expected) in tests {
+            assert_eq!(a.intersection(&b), expected, "{:?} and {:?}", a, b);
+            let flipped = b.intersection(&a);
+            let same_points = |l: Option<Line>| {
+                l.map(|l| {
+                    let mut points: Vec<_> = l.points_in_line().collect();
+                    points.sort();
+                    points
+                })
+            };
+            assert_eq!(same_points(flipped), same_points(expected));
+        }
+    }
+
+    #[test]
+    fn test_bresenham() {
+        let l = line(0, 0, 6, 3);
+        let expected = points(&[(0, 0), (1, 1), (2, 1), (3, 2), (4, 2), (5, 3), (6, 3)]);
+        assert_eq!(l.bresenham().collect::<Vec<_>>(), expected);
+
+        // Bresenham agrees with the exact points for straight and diagonal lines.
+        for l in [
+            line(0, 9, 5, 9),
+            line(7, 0, 7, 4),
+            line(8, 0, 0, 8),
+            line(3, 3, 3, 3),
+        ] {
+            assert_eq!(
+                l.bresenham().collect::<Vec<_>>(),
+                l.points_in_line().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_huge_lines() {
+        let big = 2_000_000_000;
+        let diagonal = line(-big, -big, big, big);
+        assert!(diagonal.contains(Point { x: 1, y: 1 }));
+        assert!(!diagonal.contains(Point { x: 1, y: 2 }));
+        let antidiagonal = line(-big, big, big, -big);
+        let origin = Point { x: 0, y: 0 };
+        assert_eq!(
+            diagonal.intersection(&antidiagonal),
+            Some(Line(origin, origin))
+        );
+        // These would cross at x = -6e9, which isn't an i32.
+        let (a, b) = (line(-big, 0, big, 1), line(-big, 1, big, 3));
+        assert_eq!(a.intersection(&b), None);
+    }
 }
@@ -1,30 +1,91 @@
 mod counter;
+mod heatmap;
+mod index;
 mod lines;
 mod parse;
+#[cfg(test)]
+mod rng;
+mod sweep;
 use counter::*;
 use lines::*;
 
 fn main() {
     let lines = parse::parse_input(include_str!("input.txt"));
-    let (q1, q2) = solve(&lines);
+    let (q1, q2) = solve_sweep(&lines).unwrap_or_else(|| solve(&lines, Rasterisation::Lattice));
     println!("Q1: {}", q1);
     println!("Q2: {}", q2);
-}
+    let (_, bresenham) = solve(&lines, Rasterisation::Bresenham);
+    println!("Q2 with Bresenham lines: {}", bresenham);
 
-fn solve(lines: &[Line]) -> (usize, usize) {
-    let mut straight_counter = Counter::default();
-    let mut all_counter = Counter::default();
-    for l in lines {
-        for p in l.points_in_line() {
-            if l.is_straight() {
-                straight_counter.add(p);
+    // Where are the worst danger zones?
+    let index = index::LineIndex::new(&lines);
+    let everything = index::Rect {
+        min: Point {
+            x: i32::MIN,
+            y: i32::MIN,
+        },
+        max: Point {
+            x: i32::MAX,
+            y: i32::MAX,
+        },
+    };
+    let intersections = index.intersections();
+    println!("{} pairs of lines intersect", intersections.len());
+    for k in 3.. {
+        let points = index.covered_at_least(everything, k);
+        let Some(&example) = points.first() else {
+            break;
+        };
+        println!(
+            "{} points are covered by {} lines, e.g. {:?} by lines {:?}",
+            points.len(),
+            k,
+            example,
+            index.lines_through(example)
+        );
+    }
+
+    // Pass a path (without an extension) to save a heatmap of the vent field there.
+    if let Some(path) = std::env::args().nth(1) {
+        let counts = count_points(&lines, Rasterisation::Lattice);
+        if let Some(heatmap) = heatmap::Heatmap::new(&counts, &lines) {
+            let path = format!("{}.{}", path, heatmap.extension());
+            match std::fs::write(&path, heatmap.as_bytes()) {
+                Ok(()) => println!("Saved heatmap to {}", path),
+                Err(e) => eprintln!("Couldn't save heatmap to {}: {}", path, e),
             }
-            all_counter.add(p);
         }
     }
+}
+
+/// Count the points where at least two straight lines overlap, and where at least two lines of
+/// any slope overlap.
+fn solve(lines: &[Line], rasterisation: Rasterisation) -> (usize, usize) {
+    let straight: Vec<_> = lines.iter().filter(|l| l.is_straight()).copied().collect();
+    let straight_counter = count_points(&straight, rasterisation);
+    let all_counter = count_points(lines, rasterisation);
     (straight_counter.count_ge(2), all_counter.count_ge(2))
 }
 
+/// How many lines cover each point?
+fn count_points(lines: &[Line], rasterisation: Rasterisation) -> Counter<Point> {
+    let mut counter = Counter::default();
+    for l in lines {
+        for p in l.points(rasterisation) {
+            counter.add(p);
+        }
+    }
+    counter
+}
+
+/// Like `solve`, but counts overlaps from the lines' endpoints instead of visiting every point.
+/// Returns None if any line isn't horizontal, vertical or 45°.
+fn solve_sweep(lines: &[Line]) -> Option<(usize, usize)> {
+    let straight = sweep::count_overlaps(lines.iter().filter(|l| l.is_straight()))?;
+    let all = sweep::count_overlaps(lines)?;
+    Some((straight, all))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,8 +93,28 @@ mod tests {
     #[test]
     fn test_solve_example() {
         let lines: Vec<_> = parse::parse_input(include_str!("example.txt"));
-        let (q1, q2) = solve(&lines);
-        assert_eq!(q1, 5);
-        assert_eq!(q2, 12);
+        for rasterisation in [Rasterisation::Lattice, Rasterisation::Bresenham] {
+            let (q1, q2) = solve(&lines, rasterisation);
+            assert_eq!(q1, 5);
+            assert_eq!(q2, 12);
+        }
+    }
+
+    #[test]
+    fn test_solve_sweep() {
+        let lines: Vec<_> = parse::parse_input(include_str!("input.txt"));
+        assert_eq!(
+            solve_sweep(&lines),
+            Some(solve(&lines, Rasterisation::Lattice))
+        );
+    }
+
+    #[test]
+    fn test_solve_sloped() {
+        // The first line passes through (2,1) and (4,2), but not (3,1) or (3,2).
+        let lines = parse::parse_input("0,0 -> 6,3\n2,1 -> 4,1\n3,2 -> 4,2");
+        assert_eq!(solve(&lines, Rasterisation::Lattice), (0, 2));
+        // Bresenham draws (3,2) as well.
+        assert_eq!(solve(&lines, Rasterisation::Bresenham), (0, 3));
     }
 }
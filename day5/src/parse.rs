@@ -2,9 +2,9 @@ use crate::lines::{Line, Point};
 use nom::{
     bytes::complete::tag,
     character::complete::{char, digit1, line_ending},
-    combinator::{map, map_res},
+    combinator::{map, map_res, opt, recognize},
     multi::separated_list1,
-    sequence::separated_pair,
+    sequence::{pair, separated_pair},
     IResult,
 };
 use std::str::FromStr;
@@ -29,9 +29,9 @@ impl Point {
     }
 }
 
-/// Parse a `u32` from the start of the input string.
-pub fn parse_numbers(input: &str) -> IResult<&str, u32> {
-    map_res(digit1, u32::from_str)(input)
+/// Parse an `i32`, which might start with a minus sign, from the start of the input string.
+pub fn parse_numbers(input: &str) -> IResult<&str, i32> {
+    map_res(recognize(pair(opt(char('-')), digit1)), i32::from_str)(input)
 }
 
 /// Parse the whole problem input.
@@ -50,6 +50,8 @@ mod tests {
         let tests = [
             ("1,2", Point { x: 1, y: 2 }, ""),
             ("1,2asdf", Point { x: 1, y: 2 }, "asdf"),
+            ("-1,2", Point { x: -1, y: 2 }, ""),
+            ("-10,-20", Point { x: -10, y: -20 }, ""),
         ];
         for (input, expected_output, expected_remaining_input) in tests {
             let (remaining_input, output) = Point::parse(input).unwrap();
@@ -71,6 +73,11 @@ mod tests {
                 Line(Point { x: 0, y: 9 }, Point { x: 5, y: 9 }),
                 "xyz",
             ),
+            (
+                "-3,4 -> 5,-6",
+                Line(Point { x: -3, y: 4 }, Point { x: 5, y: -6 }),
+                "",
+            ),
         ];
         for (input, expected_output, expected_remaining_input) in tests {
             let (remaining_input, output) = Line::parse(input).unwrap();
@@ -79,6 +86,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_invalid_numbers() {
+        assert!(parse_numbers("-").is_err());
+        assert!(parse_numbers("--1").is_err());
+        assert!(parse_numbers("+1").is_err());
+    }
+
     #[test]
     fn test_parse_example() {
         let input = include_str!("example.txt");
@@ -0,0 +1,325 @@
+//! Counts overlapping points directly from line endpoints, without visiting every point.
+//!
+//! Every horizontal, vertical or 45° line lies along a *carrier*: a whole row, column or diagonal.
+//! Lines on the same carrier overlap along intervals, which are found by sorting and merging.
+//! Lines on different carriers cross at no more than one point each, which are found by searching
+//! the carriers in sorted order.
+use crate::lines::Line;
+use std::collections::BTreeMap;
+
+/// Which way a line points.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+enum Orientation {
+    /// Carrier is `y`, position along it is `x`.
+    Horizontal,
+    /// Carrier is `x`, position along it is `y`.
+    Vertical,
+    /// x and y increase together. Carrier is `x - y`, position along it is `x`.
+    Diagonal,
+    /// y decreases as x increases. Carrier is `x + y`, position along it is `x`.
+    Antidiagonal,
+}
+
+const ORIENTATIONS: [Orientation; 4] = [
+    Orientation::Horizontal,
+    Orientation::Vertical,
+    Orientation::Diagonal,
+    Orientation::Antidiagonal,
+];
+
+impl Orientation {
+    /// Which carrier of this orientation passes through the point, and how far along it is the point?
+    fn locate(self, x: i64, y: i64) -> (i64, i64) {
+        match self {
+            Orientation::Horizontal => (y, x),
+            Orientation::Vertical => (x, y),
+            Orientation::Diagonal => (x - y, x),
+            Orientation::Antidiagonal => (x + y, x),
+        }
+    }
+}
+
+/// A closed range of positions along a carrier.
+type Interval = (i64, i64);
+
+/// A stretch of a carrier which is covered by at least one line.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    carrier: i64,
+    interval: Interval,
+}
+
+impl Segment {
+    /// Where does this segment cross the other one, if anywhere?
+    fn crossing(
+        &self,
+        orientation: Orientation,
+        other: &Segment,
+        other_orientation: Orientation,
+    ) -> Option<(i64, i64)> {
+        use Orientation::*;
+        let (a, b) = (self.carrier, other.carrier);
+        // Solve for the point on both carriers.
+        let (x, y) = match (orientation, other_orientation) {
+            (Horizontal, Vertical) => (b, a),
+            (Horizontal, Diagonal) => (b + a, a),
+            (Horizontal, Antidiagonal) => (b - a, a),
+            (Vertical, Diagonal) => (a, a - b),
+            (Vertical, Antidiagonal) => (a, b - a),
+            (Diagonal, Antidiagonal) => {
+                // Diagonals only cross at a grid point if their carriers have the same parity.
+                if (a + b) % 2 != 0 {
+                    return None;
+                }
+                ((a + b) / 2, (b - a) / 2)
+            }
+            _ => return other.crossing(other_orientation, self, orientation),
+        };
+        let on = |segment: &Segment, orientation: Orientation| {
+            let (_, position) = orientation.locate(x, y);
+            segment.interval.0 <= position && position <= segment.interval.1
+        };
+        (on(self, orientation) && on(other, other_orientation)).then_some((x, y))
+    }
+
+    /// Which carriers of the other orientation could this segment cross?
+    fn carriers_crossed(&self, orientation: Orientation, other: Orientation) -> Interval {
+        let endpoint = |position| match orientation {
+            Orientation::Horizontal => (position, self.carrier),
+            Orientation::Vertical => (self.carrier, position),
+            Orientation::Diagonal => (position, position - self.carrier),
+            Orientation::Antidiagonal => (position, self.carrier - position),
+        };
+        let (x0, y0) = endpoint(self.interval.0);
+        let (x1, y1) = endpoint(self.interval.1);
+        let (c0, c1) = (other.locate(x0, y0).0, other.locate(x1, y1).0);
+        (c0.min(c1), c0.max(c1))
+    }
+}
+
+/// All the lines of one orientation, grouped by carrier.
+#[derive(Default)]
+struct Carriers {
+    /// Points covered by at least one line, as merged intervals sorted by carrier.
+    covered: Vec<Segment>,
+    /// Points covered by at least two lines, as merged intervals for each carrier.
+    overlaps: BTreeMap<i64, Vec<Interval>>,
+}
+
+impl Carriers {
+    fn new(mut lines: Vec<Segment>) -> Self {
+        lines.sort_unstable_by_key(|segment| (segment.carrier, segment.interval));
+        let mut carriers = Self::default();
+        for group in lines.chunk_by(|a, b| a.carrier == b.carrier) {
+            let carrier = group[0].carrier;
+            let mut overlaps = Vec::new();
+            let mut covered: Option<Interval> = None;
+            for segment in group {
+                let (lo, hi) = segment.interval;
+                match covered {
+                    // Everything from `lo` up to the furthest earlier end is covered twice.
+                    Some((start, end)) if lo <= end => {
+                        merge(&mut overlaps, (lo, hi.min(end)));
+                        covered = Some((start, end.max(hi)));
+                    }
+                    _ => {
+                        if let Some(interval) = covered {
+                            carriers.covered.push(Segment { carrier, interval });
+                        }
+                        covered = Some((lo, hi));
+                    }
+                }
+            }
+            if let Some(interval) = covered {
+                carriers.covered.push(Segment { carrier, interval });
+            }
+            if !overlaps.is_empty() {
+                carriers.overlaps.insert(carrier, overlaps);
+            }
+        }
+        carriers
+    }
+
+    /// Is the point covered by two lines of this orientation?
+    fn overlaps_at(&self, orientation: Orientation, x: i64, y: i64) -> bool {
+        let (carrier, position) = orientation.locate(x, y);
+        let Some(intervals) = self.overlaps.get(&carrier) else {
+            return false;
+        };
+        let i = intervals.partition_point(|&(_, hi)| hi < position);
+        intervals.get(i).is_some_and(|&(lo, _)| lo <= position)
+    }
+
+    /// The covered segments whose carrier is in the given range.
+    fn on_carriers(&self, (lo, hi): Interval) -> &[Segment] {
+        let start = self.covered.partition_point(|s| s.carrier < lo);
+        let end = self.covered.partition_point(|s| s.carrier <= hi);
+        &self.covered[start..end]
+    }
+}
+
+/// Add an interval to a list of sorted, disjoint intervals, where it starts at or after every
+/// interval already in the list.
+fn merge(intervals: &mut Vec<Interval>, (lo, hi): Interval) {
+    match intervals.last_mut() {
+        Some(last) if lo <= last.1 + 1 => last.1 = last.1.max(hi),
+        _ => intervals.push((lo, hi)),
+    }
+}
+
+/// Count the points where at least two lines overlap.
+/// Returns None if any line isn't horizontal, vertical or 45°.
+pub fn count_overlaps<'a>(lines: impl IntoIterator<Item = &'a Line>) -> Option<usize> {
+    let mut by_orientation: [Vec<Segment>; 4] = Default::default();
+    for line in lines {
+        let (x0, y0) = (line.0.x as i64, line.0.y as i64);
+        let (x1, y1) = (line.1.x as i64, line.1.y as i64);
+        let orientation = if y0 == y1 {
+            Orientation::Horizontal
+        } else if x0 == x1 {
+            Orientation::Vertical
+        } else if x1 - x0 == y1 - y0 {
+            Orientation::Diagonal
+        } else if x1 - x0 == y0 - y1 {
+            Orientation::Antidiagonal
+        } else {
+            return None;
+        };
+        let (carrier, p0) = orientation.locate(x0, y0);
+        let (_, p1) = orientation.locate(x1, y1);
+        by_orientation[orientation as usize].push(Segment {
+            carrier,
+            interval: (p0.min(p1), p0.max(p1)),
+        });
+    }
+    let carriers = by_orientation.map(Carriers::new);
+
+    // Points where lines on the same carrier overlap.
+    let same_carrier: i64 = carriers
+        .iter()
+        .flat_map(|c| c.overlaps.values().flatten())
+        .map(|(lo, hi)| hi - lo + 1)
+        .sum();
+
+    // Points where lines on different carriers cross.
+    let mut crossings = Vec::new();
+    for (i, &a) in ORIENTATIONS.iter().enumerate() {
+        for &b in &ORIENTATIONS[i + 1..] {
+            for segment in &carriers[a as usize].covered {
+                let candidates = carriers[b as usize].on_carriers(segment.carriers_crossed(a, b));
+                crossings.extend(
+                    candidates
+                        .iter()
+                        .filter_map(|other| segment.crossing(a, other, b)),
+                );
+            }
+        }
+    }
+    crossings.sort_unstable();
+    crossings.dedup();
+    let mut total = same_carrier;
+    for (x, y) in crossings {
+        let overlapping = ORIENTATIONS
+            .iter()
+            .filter(|&&o| carriers[o as usize].overlaps_at(o, x, y))
+            .count() as i64;
+        if overlapping == 0 {
+            // A new point, only covered because different carriers cross here.
+            total += 1;
+        } else {
+            // Already counted once for each carrier it overlaps on, but it's only one point.
+            total -= overlapping - 1;
+        }
+    }
+
+    Some(total as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::Counter;
+    use crate::lines::Point;
+    use crate::parse::parse_input;
+    use crate::rng::Rng;
+    use std::time::Instant;
+
+    /// Count overlaps by visiting every point of every line.
+    fn count_with_counter(lines: &[Line]) -> usize {
+        let mut counter = Counter::default();
+        for p in lines.iter().flat_map(Line::points_in_line) {
+            counter.add(p);
+        }
+        counter.count_ge(2)
+    }
+
+    /// Generate random horizontal, vertical and diagonal lines, starting inside a square grid
+    /// centred on the origin.
+    fn random_lines(rng: &mut Rng, n: usize, size: i32) -> Vec<Line> {
+        let mut random = |below: i32| rng.below(below as usize) as i32;
+        (0..n)
+            .map(|_| {
+                let start = Point {
+                    x: random(size) - size / 2,
+                    y: random(size) - size / 2,
+                };
+                let len = random(size / 4);
+                let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1)][random(4) as usize];
+                let end = Point {
+                    x: start.x + dx * len,
+                    y: start.y + dy * len,
+                };
+                if random(2) == 0 {
+                    Line(start, end)
+                } else {
+                    Line(end, start)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let lines = parse_input(include_str!("example.txt"));
+        let straight: Vec<_> = lines.iter().filter(|l| l.is_straight()).copied().collect();
+        assert_eq!(count_overlaps(&straight), Some(5));
+        assert_eq!(count_overlaps(&lines), Some(12));
+    }
+
+    #[test]
+    fn test_matches_counter() {
+        let mut rng = Rng::new(1);
+        for lines in [
+            parse_input(include_str!("input.txt")),
+            random_lines(&mut rng, 300, 50),
+            random_lines(&mut rng, 300, 51),
+            random_lines(&mut rng, 1000, 200),
+        ] {
+            assert_eq!(count_overlaps(&lines), Some(count_with_counter(&lines)));
+        }
+    }
+
+    #[test]
+    fn test_unsupported_slope() {
+        let lines = parse_input("0,0 -> 6,3");
+        assert_eq!(count_overlaps(&lines), None);
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture` to compare the two approaches.
+    #[test]
+    #[ignore]
+    fn bench_sweep_against_counter() {
+        let lines = random_lines(&mut Rng::new(2021), 5_000, 10_000);
+        let start = Instant::now();
+        let expected = count_with_counter(&lines);
+        let counter_time = start.elapsed();
+        let start = Instant::now();
+        let actual = count_overlaps(&lines).unwrap();
+        let sweep_time = start.elapsed();
+        assert_eq!(actual, expected);
+        println!(
+            "{} overlaps. Counter took {:?}, sweep took {:?}",
+            actual, counter_time, sweep_time
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Answers population queries from a precomputed table, instead of simulating each school.
+//!
+//! Fish don't interact, so every fish in the same state has the same number of descendants, and a
+//! school's population is just a weighted sum of one row of the table.
+use crate::lifecycle::{Lifecycle, School};
+use crate::Quantity;
+
+/// How many fish a single fish becomes, for each starting state and number of days.
+pub struct Descendants {
+    lifecycle: Lifecycle,
+    /// `table[days][state]` is how many fish (including itself, if it's still alive) one fish in
+    /// that state becomes after that many days.
+    table: Vec<Vec<Quantity>>,
+}
+
+impl Descendants {
+    /// Tabulate every number of days up to `max_days`, or until a count would overflow, whichever
+    /// comes first.
+    pub fn new(lifecycle: Lifecycle, max_days: usize) -> Self {
+        let mut table = vec![vec![1; lifecycle.states()]];
+        while table.len() <= max_days {
+            let Some(row) = Self::next_row(&lifecycle, table.last().unwrap()) else {
+                break;
+            };
+            table.push(row);
+        }
+        Self { lifecycle, table }
+    }
+
+    /// Work out tomorrow's row from today's: a fish's descendants after `d + 1` days are the
+    /// descendants after `d` days of whatever it becomes tomorrow, and of any children it has.
+    fn next_row(lifecycle: &Lifecycle, row: &[Quantity]) -> Option<Vec<Quantity>> {
+        (0..row.len())
+            .map(|state| {
+                let (next, reproduces) = lifecycle.step(state);
+                let survivor = next.map_or(0, |next| row[next]);
+                let children = if reproduces {
+                    lifecycle
+                        .litter
+                        .checked_mul(row[lifecycle.newborn_state()])?
+                } else {
+                    0
+                };
+                survivor.checked_add(children)
+            })
+            .collect()
+    }
+
+    /// The most days the table covers.
+    pub fn max_days(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    /// How many fish one fish with this timer becomes after this many days, or None if the
+    /// timer is impossible or the days are past the end of the table.
+    pub fn descendants(&self, timer: usize, days: usize) -> Option<Quantity> {
+        let state = self.lifecycle.state_for_timer(timer)?;
+        Some(self.table.get(days)?[state])
+    }
+
+    /// How many fish the school becomes after this many days, or None if the days are past the
+    /// end of the table or the population overflows.
+    pub fn population(&self, school: &School, days: usize) -> Option<Quantity> {
+        assert_eq!(
+            school.lifecycle, self.lifecycle,
+            "school is a different species"
+        );
+        self.table
+            .get(days)?
+            .iter()
+            .zip(&school.counts)
+            .try_fold(0 as Quantity, |sum, (&descendants, &quantity)| {
+                sum.checked_add(descendants.checked_mul(quantity)?)
+            })
+    }
+
+    /// The first day the school's population is more than `threshold`, or None if that doesn't
+    /// happen within the table.
+    pub fn first_day_exceeding(&self, school: &School, threshold: Quantity) -> Option<usize> {
+        // Once the population overflows it's certainly past any threshold, but only if every
+        // fish lives forever. Otherwise, give up rather than guess.
+        let immortal = self.lifecycle.max_age.is_none();
+        (0..=self.max_days()).find(|&days| match self.population(school, days) {
+            Some(population) => population > threshold,
+            None => immortal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_problem, simulate};
+
+    #[test]
+    fn test_descendants() {
+        let table = Descendants::new(Lifecycle::LANTERNFISH, 256);
+        assert_eq!(table.max_days(), 256);
+        // A fish with timer 3 reproduces on days 4, 11 and 18.
+        assert_eq!(table.descendants(3, 0), Some(1));
+        assert_eq!(table.descendants(3, 4), Some(2));
+        assert_eq!(table.descendants(3, 11), Some(3));
+        assert_eq!(table.descendants(3, 18), Some(5));
+        assert_eq!(table.descendants(9, 18), None);
+        assert_eq!(table.descendants(3, 257), None);
+    }
+
+    #[test]
+    fn test_population_matches_simulate() {
+        let mortal = Lifecycle {
+            reset: 3,
+            newborn: 5,
+            litter: 2,
+            max_age: Some(12),
+        };
+        for lifecycle in [Lifecycle::LANTERNFISH, mortal] {
+            let school = parse_problem(include_str!("example.txt"), lifecycle);
+            let table = Descendants::new(lifecycle, 100);
+            for days in 0..=100 {
+                assert_eq!(
+                    table.population(&school, days),
+                    Some(simulate(school.clone(), days).total())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stops_before_overflow() {
+        let table = Descendants::new(Lifecycle::LANTERNFISH, 10_000);
+        assert!(table.max_days() < 10_000);
+        assert!(table.descendants(0, table.max_days()).is_some());
+    }
+
+    #[test]
+    fn test_first_day_exceeding() {
+        let school = parse_problem(include_str!("example.txt"), Lifecycle::LANTERNFISH);
+        let table = Descendants::new(Lifecycle::LANTERNFISH, 256);
+        // 26 fish after 18 days, 5934 after 80.
+        assert_eq!(table.first_day_exceeding(&school, 4), Some(0));
+        let day = table.first_day_exceeding(&school, 5933).unwrap();
+        assert!(day <= 80);
+        assert!(table.population(&school, day).unwrap() > 5933);
+        assert!(table.population(&school, day - 1).unwrap() <= 5933);
+        assert_eq!(table.first_day_exceeding(&school, Quantity::MAX), None);
+    }
+}
@@ -0,0 +1,465 @@
+//! Several species living together, where the species affect each other.
+//!
+//! An ecosystem is described by a small config file, one item per line:
+//!
+//! ```text
+//! # Comments start with a hash.
+//! species lanternfish reset=6 newborn=8 litter=1 fish=3,4,3,1,2
+//! species shark reset=20 newborn=30 max_age=200 fish=20,25
+//! # Each shark eats 50 lanternfish a day, and starves without them.
+//! predation shark lanternfish 50
+//! # There's only room for a million lanternfish.
+//! capacity lanternfish 1000000
+//! ```
+//!
+//! Species settings which aren't given are the same as lanternfish, and `max_age=none` means the
+//! species lives forever. Each day every species ages and reproduces, then the predation rules
+//! apply in order, then the carrying capacities.
+use crate::lifecycle::{Lifecycle, School};
+use crate::Quantity;
+use std::fmt;
+
+/// A rule for how species affect each other, applied once a day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Each predator eats up to `appetite` prey a day. Predators which can't eat that much die.
+    Predation {
+        predator: usize,
+        prey: usize,
+        appetite: Quantity,
+    },
+    /// The species can't grow beyond this population. Any extra fish die.
+    Capacity { species: usize, limit: Quantity },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Species {
+    pub name: String,
+    pub school: School,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ecosystem {
+    pub species: Vec<Species>,
+    pub rules: Vec<Rule>,
+}
+
+/// A species grew too big to count on this day of a run.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub species: String,
+    pub day: usize,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {}: too many {} to count", self.day, self.species)
+    }
+}
+
+/// Why a config couldn't be parsed. Lines are numbered from 1.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The line doesn't start with `species`, `predation` or `capacity`, or has the wrong number
+    /// of words for its kind.
+    BadLine {
+        line: usize,
+    },
+    /// A `species` setting that isn't `reset`, `newborn`, `litter`, `max_age` or `fish`.
+    UnknownSetting {
+        line: usize,
+        setting: String,
+    },
+    BadValue {
+        line: usize,
+        value: String,
+    },
+    /// A rule mentions a species which hasn't been declared yet.
+    UnknownSpecies {
+        line: usize,
+        name: String,
+    },
+    DuplicateSpecies {
+        line: usize,
+        name: String,
+    },
+    /// A fish's timer is impossible for its species.
+    ImpossibleTimer {
+        line: usize,
+        timer: usize,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::BadLine { line } => write!(f, "line {}: can't understand this", line),
+            ConfigError::UnknownSetting { line, setting } => {
+                write!(f, "line {}: unknown setting {}", line, setting)
+            }
+            ConfigError::BadValue { line, value } => {
+                write!(f, "line {}: {} isn't a valid value", line, value)
+            }
+            ConfigError::UnknownSpecies { line, name } => {
+                write!(f, "line {}: no species called {}", line, name)
+            }
+            ConfigError::DuplicateSpecies { line, name } => {
+                write!(
+                    f,
+                    "line {}: there's already a species called {}",
+                    line, name
+                )
+            }
+            ConfigError::ImpossibleTimer { line, timer } => {
+                write!(
+                    f,
+                    "line {}: no fish of this species can have timer {}",
+                    line, timer
+                )
+            }
+        }
+    }
+}
+
+impl Ecosystem {
+    pub fn parse(config: &str) -> Result<Self, ConfigError> {
+        let mut ecosystem = Ecosystem {
+            species: Vec::new(),
+            rules: Vec::new(),
+        };
+        for (i, text) in config.lines().enumerate() {
+            let line = i + 1;
+            let words: Vec<_> = text.split('#').next().unwrap().split_whitespace().collect();
+            let number = |value: &str| {
+                value.parse().map_err(|_| ConfigError::BadValue {
+                    line,
+                    value: value.to_string(),
+                })
+            };
+            match words[..] {
+                [] => {}
+                ["species", name, ref settings @ ..] => {
+                    if ecosystem.find(line, name).is_ok() {
+                        return Err(ConfigError::DuplicateSpecies {
+                            line,
+                            name: name.to_string(),
+                        });
+                    }
+                    let species = Species::parse(line, name, settings)?;
+                    ecosystem.species.push(species);
+                }
+                ["predation", predator, prey, appetite] => {
+                    ecosystem.rules.push(Rule::Predation {
+                        predator: ecosystem.find(line, predator)?,
+                        prey: ecosystem.find(line, prey)?,
+                        appetite: number(appetite)?,
+                    });
+                }
+                ["capacity", species, limit] => {
+                    ecosystem.rules.push(Rule::Capacity {
+                        species: ecosystem.find(line, species)?,
+                        limit: number(limit)?,
+                    });
+                }
+                _ => return Err(ConfigError::BadLine { line }),
+            }
+        }
+        Ok(ecosystem)
+    }
+
+    /// The index of the species with this name.
+    fn find(&self, line: usize, name: &str) -> Result<usize, ConfigError> {
+        self.species
+            .iter()
+            .position(|s| s.name == name)
+            .ok_or_else(|| ConfigError::UnknownSpecies {
+                line,
+                name: name.to_string(),
+            })
+    }
+
+    pub fn populations(&self) -> Vec<Quantity> {
+        self.species.iter().map(|s| s.school.total()).collect()
+    }
+
+    /// Advance every species by one day, then apply the rules. Fails with the index of the first
+    /// species which grew too big to count.
+    pub fn step(&mut self) -> Result<(), usize> {
+        for (i, species) in self.species.iter_mut().enumerate() {
+            species.school.step().map_err(|_| i)?;
+        }
+        for &rule in &self.rules {
+            match rule {
+                Rule::Predation {
+                    predator,
+                    prey,
+                    appetite,
+                } => {
+                    let predators = self.species[predator].school.total();
+                    let available = self.species[prey].school.total();
+                    let eaten = available.min(predators.saturating_mul(appetite));
+                    cull(&mut self.species[prey].school, available - eaten);
+                    // Only the predators which got a full meal survive.
+                    let fed = eaten.checked_div(appetite).unwrap_or(predators);
+                    cull(&mut self.species[predator].school, fed);
+                }
+                Rule::Capacity { species, limit } => {
+                    let school = &mut self.species[species].school;
+                    cull(school, school.total().min(limit));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the ecosystem for this many days. Returns each species' population on each day,
+    /// starting with today.
+    pub fn run(&mut self, days: usize) -> Result<Vec<Vec<Quantity>>, Overflow> {
+        let mut history = vec![self.populations()];
+        for day in 1..=days {
+            self.step().map_err(|i| Overflow {
+                species: self.species[i].name.clone(),
+                day,
+            })?;
+            history.push(self.populations());
+        }
+        Ok(history)
+    }
+
+    /// Write a history from `run` as CSV, with a column for the day and one for each species.
+    pub fn to_csv(&self, history: &[Vec<Quantity>]) -> String {
+        let mut csv = String::from("day");
+        for species in &self.species {
+            csv.push(',');
+            csv.push_str(&species.name);
+        }
+        csv.push('\n');
+        for (day, populations) in history.iter().enumerate() {
+            csv.push_str(&day.to_string());
+            for population in populations {
+                csv.push(',');
+                csv.push_str(&population.to_string());
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+impl Species {
+    fn parse(line: usize, name: &str, settings: &[&str]) -> Result<Self, ConfigError> {
+        let bad_value = |value: &str| ConfigError::BadValue {
+            line,
+            value: value.to_string(),
+        };
+        let number = |value: &str| value.parse().map_err(|_| bad_value(value));
+        let mut lifecycle = Lifecycle::LANTERNFISH;
+        let mut timers = Vec::new();
+        for setting in settings {
+            let (key, value) = setting.split_once('=').ok_or_else(|| bad_value(setting))?;
+            match key {
+                "reset" => lifecycle.reset = number(value)?,
+                "newborn" => lifecycle.newborn = number(value)?,
+                "litter" => lifecycle.litter = number(value)? as Quantity,
+                "max_age" if value == "none" => lifecycle.max_age = None,
+                "max_age" => lifecycle.max_age = Some(number(value)?),
+                "fish" => {
+                    timers = value.split(',').map(number).collect::<Result<_, _>>()?;
+                }
+                _ => {
+                    return Err(ConfigError::UnknownSetting {
+                        line,
+                        setting: key.to_string(),
+                    })
+                }
+            }
+        }
+        let mut school = School::new(lifecycle);
+        for timer in timers {
+            if lifecycle.state_for_timer(timer).is_none() {
+                return Err(ConfigError::ImpossibleTimer { line, timer });
+            }
+            school.add(timer);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            school,
+        })
+    }
+}
+
+/// Kill fish until only `survivors` are left, taking them from every state in proportion to how
+/// many fish are in it. Any leftover deaths come from the lowest states first.
+fn cull(school: &mut School, survivors: Quantity) {
+    let total = school.total();
+    if survivors >= total {
+        return;
+    }
+    let mut deaths = total - survivors;
+    let planned: Vec<_> = school
+        .counts
+        .iter()
+        .map(|&n| (n as u128 * deaths as u128 / total as u128) as Quantity)
+        .collect();
+    for (n, planned) in school.counts.iter_mut().zip(planned) {
+        *n -= planned;
+        deaths -= planned;
+    }
+    for n in &mut school.counts {
+        let extra = deaths.min(*n);
+        *n -= extra;
+        deaths -= extra;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulate;
+
+    #[test]
+    fn test_parse() {
+        let config = "\
+# Lanternfish and sharks
+species lanternfish fish=3,4,3,1,2
+
+species shark reset=20 newborn=30 max_age=200 fish=20
+predation shark lanternfish 2  # hungry
+capacity lanternfish 100
+";
+        let ecosystem = Ecosystem::parse(config).unwrap();
+        assert_eq!(ecosystem.species.len(), 2);
+        assert_eq!(
+            ecosystem.species[0].school.lifecycle,
+            Lifecycle::LANTERNFISH
+        );
+        assert_eq!(
+            ecosystem.species[1].school.lifecycle,
+            Lifecycle {
+                reset: 20,
+                newborn: 30,
+                litter: 1,
+                max_age: Some(200)
+            }
+        );
+        assert_eq!(ecosystem.populations(), vec![5, 1]);
+        assert_eq!(
+            ecosystem.rules,
+            vec![
+                Rule::Predation {
+                    predator: 1,
+                    prey: 0,
+                    appetite: 2
+                },
+                Rule::Capacity {
+                    species: 0,
+                    limit: 100
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tests = [
+            ("fish", ConfigError::BadLine { line: 1 }),
+            (
+                "species a colour=red",
+                ConfigError::UnknownSetting {
+                    line: 1,
+                    setting: "colour".to_string(),
+                },
+            ),
+            (
+                "species a\nspecies a",
+                ConfigError::DuplicateSpecies {
+                    line: 2,
+                    name: "a".to_string(),
+                },
+            ),
+            (
+                "species a\ncapacity b 10",
+                ConfigError::UnknownSpecies {
+                    line: 2,
+                    name: "b".to_string(),
+                },
+            ),
+            (
+                "species a litter=lots",
+                ConfigError::BadValue {
+                    line: 1,
+                    value: "lots".to_string(),
+                },
+            ),
+            (
+                "species a fish=9",
+                ConfigError::ImpossibleTimer { line: 1, timer: 9 },
+            ),
+        ];
+        for (config, expected) in tests {
+            assert_eq!(Ecosystem::parse(config), Err(expected), "{}", config);
+        }
+    }
+
+    #[test]
+    fn test_no_rules_matches_simulate() {
+        let mut ecosystem = Ecosystem::parse("species fish fish=3,4,3,1,2").unwrap();
+        let school = ecosystem.species[0].school.clone();
+        let history = ecosystem.run(80).unwrap();
+        assert_eq!(history[18], vec![26]);
+        assert_eq!(history[80], vec![simulate(school, 80).total()]);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut ecosystem =
+            Ecosystem::parse("species fish fish=3,4,3,1,2\ncapacity fish 100").unwrap();
+        let history = ecosystem.run(80).unwrap();
+        assert!(history.iter().all(|day| day[0] <= 100));
+        assert_eq!(history[80], vec![100]);
+    }
+
+    #[test]
+    fn test_predation() {
+        let config = "\
+species prey reset=0 newborn=0 fish=0
+species predator max_age=none fish=8,8,8
+predation predator prey 2
+";
+        let mut ecosystem = Ecosystem::parse(config).unwrap();
+        // The prey doubles every day. On day 1 there are 2, and the predators eat both, but only
+        // one predator is fed. On day 2 there's no prey left, so the last predator starves.
+        assert_eq!(
+            ecosystem.run(3).unwrap(),
+            vec![vec![1, 3], vec![0, 1], vec![0, 0], vec![0, 0]]
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut ecosystem =
+            Ecosystem::parse("species a reset=0 newborn=0 litter=1000 fish=0").unwrap();
+        assert_eq!(
+            ecosystem.run(10),
+            Err(Overflow {
+                species: "a".to_string(),
+                day: 7
+            })
+        );
+    }
+
+    #[test]
+    fn test_cull() {
+        let mut school = School::new(Lifecycle::LANTERNFISH);
+        school.counts = vec![10, 0, 5, 0, 0, 0, 0, 0, 1];
+        cull(&mut school, 8);
+        assert_eq!(school.counts, vec![4, 0, 3, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut ecosystem = Ecosystem::parse("species a fish=1\nspecies b fish=0,0").unwrap();
+        let history = ecosystem.run(2).unwrap();
+        assert_eq!(ecosystem.to_csv(&history), "day,a,b\n0,1,2\n1,1,4\n2,2,4\n");
+    }
+}
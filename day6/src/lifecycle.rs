@@ -0,0 +1,221 @@
+use crate::Quantity;
+
+/// How a species of fish ages and reproduces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lifecycle {
+    /// After reproducing, a fish's timer goes back to this. It reproduces again when the timer
+    /// passes 0, so there are `reset + 1` days between litters.
+    pub reset: usize,
+    /// Newborn fish start with this timer.
+    pub newborn: usize,
+    /// How many fish are born each time a fish reproduces.
+    pub litter: Quantity,
+    /// Fish die on the day they'd become older than this. If this is None, fish live forever.
+    pub max_age: Option<usize>,
+}
+
+impl Lifecycle {
+    /// The species from the puzzle.
+    pub const LANTERNFISH: Self = Self {
+        reset: 6,
+        newborn: 8,
+        litter: 1,
+        max_age: None,
+    };
+
+    /// How many different states a fish can be in.
+    /// Immortal fish are grouped by their timer, but mortal fish have to be grouped by their age.
+    /// A fish's age determines its timer, but not the other way round.
+    pub fn states(&self) -> usize {
+        match self.max_age {
+            None => self.reset.max(self.newborn) + 1,
+            Some(max_age) => max_age + 1,
+        }
+    }
+
+    /// The state of a fish with the given timer, or None if no fish can have that timer.
+    /// Mortal fish whose age isn't known are assumed to be the youngest age with that timer.
+    pub fn state_for_timer(&self, timer: usize) -> Option<usize> {
+        match self.max_age {
+            None => (timer < self.states()).then_some(timer),
+            Some(max_age) => {
+                let age = self.newborn.checked_sub(timer)?;
+                (age <= max_age).then_some(age)
+            }
+        }
+    }
+
+    /// The state newborn fish start in.
+    pub fn newborn_state(&self) -> usize {
+        match self.max_age {
+            None => self.newborn,
+            Some(_) => 0,
+        }
+    }
+
+    /// What happens to a fish in this state over a day? Returns the state it'll be in tomorrow,
+    /// or None if it dies, and whether it reproduces.
+    pub fn step(&self, state: usize) -> (Option<usize>, bool) {
+        match self.max_age {
+            None if state == 0 => (Some(self.reset), true),
+            None => (Some(state - 1), false),
+            Some(max_age) => {
+                let next = (state < max_age).then_some(state + 1);
+                (next, self.timer_at_age(state) == 0)
+            }
+        }
+    }
+
+    /// The timer of a fish which is `age` days old.
+    fn timer_at_age(&self, age: usize) -> usize {
+        if age <= self.newborn {
+            self.newborn - age
+        } else {
+            // Count down from `reset` to 0, over and over.
+            self.reset - (age - self.newborn - 1) % (self.reset + 1)
+        }
+    }
+}
+
+/// There were too many fish to count.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TooManyFish;
+
+/// A school of fish, grouped by their lifecycle's states.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct School {
+    pub lifecycle: Lifecycle,
+    /// Each index is the quantity of fish in that state.
+    pub counts: Vec<Quantity>,
+}
+
+impl School {
+    pub fn new(lifecycle: Lifecycle) -> Self {
+        Self {
+            lifecycle,
+            counts: vec![0; lifecycle.states()],
+        }
+    }
+
+    /// Add a fish with the given timer. Panics if no fish of this species can have that timer.
+    pub fn add(&mut self, timer: usize) {
+        let state = self
+            .lifecycle
+            .state_for_timer(timer)
+            .unwrap_or_else(|| panic!("no fish can have timer {}", timer));
+        self.counts[state] += 1;
+    }
+
+    pub fn total(&self) -> Quantity {
+        self.counts.iter().sum()
+    }
+
+    /// Advance the school by one day. If there'd be too many fish to count, the school is left as
+    /// it was.
+    pub fn step(&mut self) -> Result<(), TooManyFish> {
+        let mut new_fish = vec![0; self.counts.len()];
+        for (state, &quantity) in self.counts.iter().enumerate() {
+            let (next, reproduces) = self.lifecycle.step(state);
+            // Each of the fish age by a day.
+            if let Some(next) = next {
+                new_fish[next] = quantity.checked_add(new_fish[next]).ok_or(TooManyFish)?;
+            }
+            // And new fish spawn.
+            if reproduces {
+                let newborn = &mut new_fish[self.lifecycle.newborn_state()];
+                let born = quantity
+                    .checked_mul(self.lifecycle.litter)
+                    .ok_or(TooManyFish)?;
+                *newborn = born.checked_add(*newborn).ok_or(TooManyFish)?;
+            }
+        }
+        // The total has to fit too, so `total` can't overflow.
+        new_fish
+            .iter()
+            .try_fold(0, |total: Quantity, &n| total.checked_add(n))
+            .ok_or(TooManyFish)?;
+        self.counts = new_fish;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_at_age() {
+        let timers: Vec<_> = (0..20)
+            .map(|age| Lifecycle::LANTERNFISH.timer_at_age(age))
+            .collect();
+        assert_eq!(
+            timers,
+            vec![8, 7, 6, 5, 4, 3, 2, 1, 0, 6, 5, 4, 3, 2, 1, 0, 6, 5, 4, 3]
+        );
+    }
+
+    #[test]
+    fn test_long_lived_matches_immortal() {
+        // Mortal fish which don't die within the simulation behave just like immortal ones.
+        let mortal = Lifecycle {
+            max_age: Some(100),
+            ..Lifecycle::LANTERNFISH
+        };
+        let mut a = School::new(Lifecycle::LANTERNFISH);
+        let mut b = School::new(mortal);
+        for timer in [3, 4, 3, 1, 2] {
+            a.add(timer);
+            b.add(timer);
+        }
+        for _ in 0..80 {
+            a.step().unwrap();
+            b.step().unwrap();
+            assert_eq!(a.total(), b.total());
+        }
+    }
+
+    #[test]
+    fn test_max_age() {
+        // One litter of three, after two days, and then the parent dies.
+        let lifecycle = Lifecycle {
+            reset: 1,
+            newborn: 2,
+            litter: 3,
+            max_age: Some(3),
+        };
+        let mut school = School::new(lifecycle);
+        school.add(2);
+        let mut totals = Vec::new();
+        for _ in 0..6 {
+            school.step().unwrap();
+            totals.push(school.total());
+        }
+        // Day 3: parent reproduces. Day 4: parent dies. Day 6: the children reproduce.
+        assert_eq!(totals, vec![1, 1, 4, 3, 3, 12]);
+    }
+
+    #[test]
+    fn test_too_many_fish() {
+        let lifecycle = Lifecycle {
+            reset: 0,
+            newborn: 0,
+            litter: 1000,
+            max_age: None,
+        };
+        let mut school = School::new(lifecycle);
+        school.add(0);
+        // 1001^6 fish fit in a u64, but 1001^7 don't.
+        for _ in 0..6 {
+            school.step().unwrap();
+        }
+        let before = school.clone();
+        assert_eq!(school.step(), Err(TooManyFish));
+        assert_eq!(school, before);
+    }
+
+    #[test]
+    #[should_panic(expected = "no fish can have timer 9")]
+    fn test_impossible_timer() {
+        School::new(Lifecycle::LANTERNFISH).add(9);
+    }
+}
@@ -1,37 +1,82 @@
-type Quantity = u64;
+mod descendants;
+mod ecosystem;
+mod lifecycle;
+mod matrix;
+use lifecycle::{Lifecycle, School};
 
-/// A school of fish, grouped by days until they next reproduce.
-/// Each index is the quantity of fish with that many days until they next reproduce.
-type School = [Quantity; 9];
+type Quantity = u64;
 
 fn main() {
-    let fish = parse_problem(include_str!("input.txt"));
-    let fish_80 = simulate(fish, 80);
-    let q1: Quantity = fish_80.iter().sum();
+    let fish = parse_problem(include_str!("input.txt"), Lifecycle::LANTERNFISH);
+    let fish_80 = simulate(fish.clone(), 80);
+    let q1: Quantity = fish_80.total();
     println!("Q1: {}", q1);
     let fish_256 = simulate(fish_80, 256 - 80);
-    let q2: Quantity = fish_256.iter().sum();
+    let q2: Quantity = fish_256.total();
     println!("Q2: {}", q2);
+
+    // When does the school get really big?
+    let table = descendants::Descendants::new(Lifecycle::LANTERNFISH, 256);
+    for timer in 0..=Lifecycle::LANTERNFISH.newborn {
+        let descendants = table.descendants(timer, 256).unwrap();
+        println!(
+            "A fish with timer {} becomes {} fish after 256 days",
+            timer, descendants
+        );
+    }
+    let threshold = 1_000_000_000;
+    if let Some(day) = table.first_day_exceeding(&fish, threshold) {
+        println!("There are over {} fish from day {}", threshold, day);
+    }
+
+    // How far can we look ahead?
+    let days = 10_000;
+    let population: num_bigint::BigUint = matrix::project_total(&fish, days).unwrap();
+    println!(
+        "After {} days there'd be a {}-digit number of fish",
+        days,
+        population.to_string().len()
+    );
+
+    // What if the lanternfish weren't alone?
+    let mut ecosystem = match ecosystem::Ecosystem::parse(include_str!("ecosystem.txt")) {
+        Ok(ecosystem) => ecosystem,
+        Err(e) => {
+            eprintln!("Error in ecosystem config: {}", e);
+            return;
+        }
+    };
+    let history = match ecosystem.run(256) {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("Error running the ecosystem: {}", e);
+            return;
+        }
+    };
+    for (species, population) in ecosystem.species.iter().zip(&history[256]) {
+        println!("After 256 days there are {} {}", population, species.name);
+    }
+    // Pass a path to save every day's populations there as CSV.
+    if let Some(path) = std::env::args().nth(1) {
+        match std::fs::write(&path, ecosystem.to_csv(&history)) {
+            Ok(()) => println!("Saved populations to {}", path),
+            Err(e) => eprintln!("Couldn't save populations to {}: {}", path, e),
+        }
+    }
 }
 
-fn parse_problem(s: &str) -> School {
-    let mut school = [0; 9];
+fn parse_problem(s: &str, lifecycle: Lifecycle) -> School {
+    let mut school = School::new(lifecycle);
     for fish in s.split(',') {
-        let days_until_reproduction: usize = fish.parse().unwrap();
-        school[days_until_reproduction] += 1;
+        let days_until_reproduction: usize = fish.trim().parse().unwrap();
+        school.add(days_until_reproduction);
     }
     school
 }
 
 fn simulate(mut fish: School, days: usize) -> School {
     for _ in 0..days {
-        let mut new_fish = [0; 9];
-        // Each of the fish age by a day.
-        new_fish[..8].clone_from_slice(&fish[1..(8 + 1)]);
-        new_fish[6] += fish[0];
-        // And new fish spawn.
-        new_fish[8] = fish[0];
-        fish = new_fish;
+        fish.step().expect("too many fish to count");
     }
     fish
 }
@@ -42,13 +87,26 @@ mod tests {
 
     #[test]
     fn test_example_small() {
-        let input = parse_problem(include_str!("example.txt"));
-        assert_eq!(simulate(input, 18).into_iter().sum::<Quantity>(), 26);
+        let input = parse_problem(include_str!("example.txt"), Lifecycle::LANTERNFISH);
+        assert_eq!(simulate(input, 18).total(), 26);
     }
 
     #[test]
     fn test_example() {
-        let input = parse_problem(include_str!("example.txt"));
-        assert_eq!(simulate(input, 80).into_iter().sum::<Quantity>(), 5934);
+        let input = parse_problem(include_str!("example.txt"), Lifecycle::LANTERNFISH);
+        assert_eq!(simulate(input, 80).total(), 5934);
+    }
+
+    #[test]
+    fn test_other_species() {
+        // Reproduces every day, two at a time, as soon as it's born.
+        let rabbits = Lifecycle {
+            reset: 0,
+            newborn: 0,
+            litter: 2,
+            max_age: None,
+        };
+        let input = parse_problem("0", rabbits);
+        assert_eq!(simulate(input, 5).total(), 3_u64.pow(5));
     }
 }
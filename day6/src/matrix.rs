@@ -0,0 +1,164 @@
+//! Projects a school far into the future without simulating every day.
+//!
+//! One day of a lifecycle is a linear map from today's counts to tomorrow's, so it can be written
+//! as a square transition matrix. Simulating `n` days multiplies by that matrix `n` times, and the
+//! matrix's `n`th power can be found with O(log n) multiplications by repeated squaring.
+use crate::lifecycle::{Lifecycle, School};
+use num_traits::{CheckedAdd, CheckedMul, One, Zero};
+
+/// A type which can count fish. Fixed-width integers report overflow, big integers never do.
+pub trait Count: Clone + Zero + One + CheckedAdd + CheckedMul + From<u64> {}
+
+impl<T: Clone + Zero + One + CheckedAdd + CheckedMul + From<u64>> Count for T {}
+
+/// A square matrix, stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Count> Matrix<T> {
+    fn zero(size: usize) -> Self {
+        Self {
+            size,
+            cells: vec![T::zero(); size * size],
+        }
+    }
+
+    fn identity(size: usize) -> Self {
+        let mut m = Self::zero(size);
+        for i in 0..size {
+            m.cells[i * size + i] = T::one();
+        }
+        m
+    }
+
+    fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[row * self.size + col]
+    }
+
+    /// The matrix which advances a school with this lifecycle by one day.
+    /// Entry (row, col) is how many fish in state `row` tomorrow come from each fish in state
+    /// `col` today.
+    pub fn transition(lifecycle: &Lifecycle) -> Self {
+        let size = lifecycle.states();
+        let mut m = Self::zero(size);
+        for state in 0..size {
+            let (next, reproduces) = lifecycle.step(state);
+            if let Some(next) = next {
+                m.cells[next * size + state] = T::one();
+            }
+            if reproduces {
+                let cell = &mut m.cells[lifecycle.newborn_state() * size + state];
+                *cell = cell.clone() + T::from(lifecycle.litter);
+            }
+        }
+        m
+    }
+
+    /// Multiply two matrices, or None if any entry overflows.
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mut product = Self::zero(self.size);
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let mut sum = T::zero();
+                for k in 0..self.size {
+                    sum = sum.checked_add(&self.get(row, k).checked_mul(other.get(k, col))?)?;
+                }
+                product.cells[row * self.size + col] = sum;
+            }
+        }
+        Some(product)
+    }
+
+    /// Raise the matrix to the given power by repeated squaring, or None if any entry overflows.
+    pub fn checked_pow(&self, mut exponent: u64) -> Option<Self> {
+        let mut result = Self::identity(self.size);
+        let mut square = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.checked_mul(&square)?;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                square = square.checked_mul(&square)?;
+            }
+        }
+        Some(result)
+    }
+
+    /// Multiply the matrix by a column of counts, or None if any count overflows.
+    pub fn checked_apply(&self, counts: &[T]) -> Option<Vec<T>> {
+        (0..self.size)
+            .map(|row| {
+                counts
+                    .iter()
+                    .enumerate()
+                    .try_fold(T::zero(), |sum, (col, n)| {
+                        sum.checked_add(&self.get(row, col).checked_mul(n)?)
+                    })
+            })
+            .collect()
+    }
+}
+
+/// The counts in each state after the given number of days, in O(log days) matrix
+/// multiplications. Returns None if a count overflows `T`. Because every power of the transition
+/// matrix is computed along the way, this can overflow even when the school itself would fit, if
+/// a state with no fish in it would have grown enormous.
+pub fn project<T: Count>(school: &School, days: u64) -> Option<Vec<T>> {
+    let counts: Vec<T> = school.counts.iter().map(|&n| T::from(n)).collect();
+    Matrix::transition(&school.lifecycle)
+        .checked_pow(days)?
+        .checked_apply(&counts)
+}
+
+/// The total number of fish after the given number of days, or None if it overflows `T`.
+pub fn project_total<T: Count>(school: &School, days: u64) -> Option<T> {
+    project(school, days)?
+        .iter()
+        .try_fold(T::zero(), |sum, n| sum.checked_add(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_problem, simulate};
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_matches_simulate() {
+        let mortal = Lifecycle {
+            reset: 2,
+            newborn: 4,
+            litter: 3,
+            max_age: Some(10),
+        };
+        for lifecycle in [Lifecycle::LANTERNFISH, mortal] {
+            let school = parse_problem(include_str!("example.txt"), lifecycle);
+            for days in 0..100 {
+                let expected = simulate(school.clone(), days).counts;
+                assert_eq!(project::<u64>(&school, days as u64), Some(expected.clone()));
+                let big: Vec<BigUint> = expected.into_iter().map(BigUint::from).collect();
+                assert_eq!(project::<BigUint>(&school, days as u64), Some(big));
+            }
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        let school = parse_problem(include_str!("example.txt"), Lifecycle::LANTERNFISH);
+        assert_eq!(project_total::<u64>(&school, 256), Some(26984457539));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let school = parse_problem(include_str!("example.txt"), Lifecycle::LANTERNFISH);
+        assert_eq!(project_total::<u64>(&school, 1000), None);
+        assert!(project_total::<u128>(&school, 500).is_some());
+        let big = project_total::<BigUint>(&school, 1000).unwrap();
+        // The population grows by about 9% a day.
+        assert_eq!(big.to_string().len(), 39);
+    }
+}
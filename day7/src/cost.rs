@@ -0,0 +1,382 @@
+//! Fuel cost functions written as expressions of the distance `d`, like `d*(d+1)/2` or
+//! `min(d, 10) + 3`.
+//!
+//! Expressions can use integers, `d`, `+ - * / ^`, brackets, and the functions `min(a, b)`,
+//! `max(a, b)` and `abs(a)`. `^` binds tightest and groups to the right, then `*` and `/`, then
+//! `+` and `-`. Division rounds towards zero.
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Distance,
+    Number(i64),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Min(Box<Expr>, Box<Expr>),
+    Max(Box<Expr>, Box<Expr>),
+    Abs(Box<Expr>),
+}
+
+impl Expr {
+    /// The cost of moving `d` steps, or None if it's undefined (dividing by 0, or a negative
+    /// power) or doesn't fit in an `i64`.
+    pub fn eval(&self, d: i64) -> Option<i64> {
+        let both = |a: &Expr, b: &Expr| Some((a.eval(d)?, b.eval(d)?));
+        match self {
+            Expr::Distance => Some(d),
+            Expr::Number(n) => Some(*n),
+            Expr::Neg(a) => a.eval(d)?.checked_neg(),
+            Expr::Add(a, b) => both(a, b).and_then(|(a, b)| a.checked_add(b)),
+            Expr::Sub(a, b) => both(a, b).and_then(|(a, b)| a.checked_sub(b)),
+            Expr::Mul(a, b) => both(a, b).and_then(|(a, b)| a.checked_mul(b)),
+            Expr::Div(a, b) => both(a, b).and_then(|(a, b)| a.checked_div(b)),
+            Expr::Pow(a, b) => both(a, b).and_then(|(a, b)| a.checked_pow(u32::try_from(b).ok()?)),
+            Expr::Min(a, b) => both(a, b).map(|(a, b)| a.min(b)),
+            Expr::Max(a, b) => both(a, b).map(|(a, b)| a.max(b)),
+            Expr::Abs(a) => a.eval(d)?.checked_abs(),
+        }
+    }
+}
+
+/// Why an expression couldn't be parsed. Positions count characters from 0.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    UnexpectedChar { position: usize, found: char },
+    UnexpectedEnd,
+    UnknownFunction { position: usize, name: String },
+    BadNumber { position: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedChar { position, found } => {
+                write!(f, "unexpected {:?} at position {}", found, position)
+            }
+            ParseError::UnexpectedEnd => write!(f, "expression ends too soon"),
+            ParseError::UnknownFunction { position, name } => {
+                write!(f, "unknown function {} at position {}", name, position)
+            }
+            ParseError::BadNumber { position } => {
+                write!(f, "number at position {} is too big", position)
+            }
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            position: 0,
+        };
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(found) => Err(ParseError::UnexpectedChar {
+                position: parser.position,
+                found,
+            }),
+        }
+    }
+}
+
+/// A recursive descent parser, with one method for each level of precedence.
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    /// The next character which isn't whitespace, without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.position += 1;
+        }
+        self.chars.get(self.position).copied()
+    }
+
+    /// Consume the next character if it's the expected one.
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+        match self.peek() {
+            None => Err(ParseError::UnexpectedEnd),
+            Some(found) => Err(ParseError::UnexpectedChar {
+                position: self.position,
+                found,
+            }),
+        }
+    }
+
+    /// Terms added or subtracted.
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.product()?;
+        loop {
+            if self.eat('+') {
+                expr = Expr::Add(Box::new(expr), Box::new(self.product()?));
+            } else if self.eat('-') {
+                expr = Expr::Sub(Box::new(expr), Box::new(self.product()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Factors multiplied or divided.
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.power()?;
+        loop {
+            if self.eat('*') {
+                expr = Expr::Mul(Box::new(expr), Box::new(self.power()?));
+            } else if self.eat('/') {
+                expr = Expr::Div(Box::new(expr), Box::new(self.power()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// An atom, possibly raised to a power.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            // Parse the exponent as another power, so `d^2^3` is `d^(2^3)`.
+            return Ok(Expr::Pow(Box::new(base), Box::new(self.power()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        let next = self.peek();
+        let start = self.position;
+        match next {
+            None => Err(ParseError::UnexpectedEnd),
+            Some('-') => {
+                self.position += 1;
+                Ok(Expr::Neg(Box::new(self.power()?)))
+            }
+            Some('(') => {
+                self.position += 1;
+                let expr = self.sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| c.is_ascii_digit())
+                {
+                    self.position += 1;
+                }
+                let digits: String = self.chars[start..self.position].iter().collect();
+                digits
+                    .parse()
+                    .map(Expr::Number)
+                    .map_err(|_| ParseError::BadNumber { position: start })
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self
+                    .chars
+                    .get(self.position)
+                    .is_some_and(|c| c.is_ascii_alphanumeric())
+                {
+                    self.position += 1;
+                }
+                let name: String = self.chars[start..self.position].iter().collect();
+                if name == "d" {
+                    return Ok(Expr::Distance);
+                }
+                self.function(start, name)
+            }
+            Some(found) => Err(ParseError::UnexpectedChar {
+                position: start,
+                found,
+            }),
+        }
+    }
+
+    /// The arguments of a function call, after its name.
+    fn function(&mut self, start: usize, name: String) -> Result<Expr, ParseError> {
+        let binary = match name.as_str() {
+            "min" => Expr::Min,
+            "max" => Expr::Max,
+            "abs" => {
+                self.expect('(')?;
+                let a = self.sum()?;
+                self.expect(')')?;
+                return Ok(Expr::Abs(Box::new(a)));
+            }
+            _ => {
+                return Err(ParseError::UnknownFunction {
+                    position: start,
+                    name,
+                })
+            }
+        };
+        self.expect('(')?;
+        let a = self.sum()?;
+        self.expect(',')?;
+        let b = self.sum()?;
+        self.expect(')')?;
+        Ok(binary(Box::new(a), Box::new(b)))
+    }
+}
+
+/// A cost expression, evaluated at every distance a crab might need to move.
+pub struct CostTable {
+    costs: Vec<i32>,
+}
+
+impl CostTable {
+    /// Evaluate the expression for distances from 0 up to `max_distance`. Returns the first
+    /// distance where it's undefined or doesn't fit in an `i32`, if there is one.
+    pub fn new(expr: &Expr, max_distance: i32) -> Result<Self, i32> {
+        let costs = (0..=max_distance)
+            .map(|d| {
+                expr.eval(d as i64)
+                    .and_then(|cost| i32::try_from(cost).ok())
+                    .ok_or(d)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { costs })
+    }
+
+    pub fn cost(&self, distance: i32) -> i32 {
+        self.costs[distance as usize]
+    }
+
+    /// Is every step at least as expensive as the one before? If so, the cost is convex and
+    /// nondecreasing, which is what `best_position` needs.
+    pub fn is_convex(&self) -> bool {
+        let steps: Vec<_> = self
+            .costs
+            .windows(2)
+            .map(|w| w[1] as i64 - w[0] as i64)
+            .collect();
+        steps.first().is_none_or(|&first| first >= 0) && steps.windows(2).all(|w| w[0] <= w[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_all(s: &str, distances: &[i64]) -> Vec<Option<i64>> {
+        let expr: Expr = s.parse().unwrap();
+        distances.iter().map(|&d| expr.eval(d)).collect()
+    }
+
+    #[test]
+    fn test_eval() {
+        let tests = [
+            ("d", vec![Some(0), Some(1), Some(5)]),
+            ("d*(d+1)/2", vec![Some(0), Some(1), Some(15)]),
+            ("d^2", vec![Some(0), Some(1), Some(25)]),
+            ("min(d, 10) + 3", vec![Some(3), Some(4), Some(8)]),
+            ("2^d^2", vec![Some(1), Some(2), Some(1 << 25)]),
+            ("-d - -2 * 3", vec![Some(6), Some(5), Some(1)]),
+            ("abs(d - 3) * max(d, 2)", vec![Some(6), Some(4), Some(10)]),
+            ("10 / d", vec![None, Some(10), Some(2)]),
+            ("d^(0-1)", vec![None, None, None]),
+            ("9999999999^d", vec![Some(1), Some(9999999999), None]),
+        ];
+        for (s, expected) in tests {
+            assert_eq!(eval_all(s, &[0, 1, 5]), expected, "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tests = [
+            ("", ParseError::UnexpectedEnd),
+            ("d +", ParseError::UnexpectedEnd),
+            (
+                "d d",
+                ParseError::UnexpectedChar {
+                    position: 2,
+                    found: 'd',
+                },
+            ),
+            ("(d", ParseError::UnexpectedEnd),
+            (
+                "min(d)",
+                ParseError::UnexpectedChar {
+                    position: 5,
+                    found: ')',
+                },
+            ),
+            (
+                "sqrt(d)",
+                ParseError::UnknownFunction {
+                    position: 0,
+                    name: "sqrt".to_string(),
+                },
+            ),
+            (
+                "d * ?",
+                ParseError::UnexpectedChar {
+                    position: 4,
+                    found: '?',
+                },
+            ),
+            (
+                "d % 2",
+                ParseError::UnexpectedChar {
+                    position: 2,
+                    found: '%',
+                },
+            ),
+            (
+                "99999999999999999999",
+                ParseError::BadNumber { position: 0 },
+            ),
+        ];
+        for (s, expected) in tests {
+            assert_eq!(s.parse::<Expr>(), Err(expected), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_convexity() {
+        let convex = |s: &str| CostTable::new(&s.parse().unwrap(), 30).unwrap().is_convex();
+        assert!(convex("d"));
+        assert!(convex("d*(d+1)/2"));
+        assert!(convex("d^2"));
+        assert!(convex("7"));
+        assert!(convex("max(d, 10)"));
+        assert!(!convex("min(d, 10) + 3"));
+        assert!(!convex("0 - d"));
+        assert!(!convex("abs(d - 10)"));
+    }
+
+    #[test]
+    fn test_undefined_cost() {
+        let expr: Expr = "100 / (d - 20)".parse().unwrap();
+        assert!(CostTable::new(&expr, 19).is_ok());
+        assert_eq!(CostTable::new(&expr, 30).err(), Some(20));
+        let expr: Expr = "d^10".parse().unwrap();
+        assert_eq!(CostTable::new(&expr, 30).err(), Some(9));
+    }
+}
@@ -1,29 +1,152 @@
+mod cost;
+#[cfg(test)]
+mod parity;
+#[cfg(test)]
+mod rng;
+mod space;
+use cost::{CostTable, Expr};
+use space::Metric;
+
 fn main() {
     let crabs = parse_positions(include_str!("input.txt"));
-    println!("Q1: {}", best_position(&crabs, |n| n));
-    println!("Q2: {}", best_position(&crabs, triangle_num));
+    let (position, q1) = median_position(&crabs);
+    println!("Q1: {} (at position {})", q1, position);
+    let (position, q2) = mean_position(&crabs);
+    println!("Q2: {} (at position {})", q2, position);
+
+    // What if crab engines worked differently?
+    for text in ["d", "d*(d+1)/2", "d^2", "min(d, 10) + 3"] {
+        match optimise(&crabs, text) {
+            Ok((position, fuel, strategy)) => println!(
+                "Costing {}: {} fuel at position {}, found by {:?}",
+                text, fuel, position, strategy
+            ),
+            Err(e) => println!("Costing {}: {}", text, e),
+        }
+    }
+
+    // And what if they could move in three dimensions?
+    let crabs = match space::parse_crabs::<3>(include_str!("example3d.txt")) {
+        Ok(crabs) => crabs,
+        Err(e) => {
+            eprintln!("Error in 3D crabs: {}", e);
+            return;
+        }
+    };
+    for metric in [Metric::Manhattan, Metric::Chebyshev, Metric::Euclidean] {
+        let (point, fuel) = space::meeting_point(&crabs, metric, |d| d);
+        println!(
+            "With {:?} distance, 3D crabs meet at {:?} using {:.2} fuel",
+            metric, point, fuel
+        );
+    }
+}
+
+/// How the best position was found.
+#[derive(PartialEq, Eq, Debug)]
+enum Strategy {
+    Search,
+    BruteForce,
+}
+
+/// Find the best position for a cost given as an expression, using a binary search if the cost
+/// is convex, or else trying every position. Returns the position, its cost, and the strategy.
+fn optimise(crabs: &[i32], cost: &str) -> Result<(i32, i64, Strategy), String> {
+    let expr: Expr = cost
+        .parse()
+        .map_err(|e| format!("can't parse cost: {}", e))?;
+    let max_distance = crabs.iter().max().unwrap() - crabs.iter().min().unwrap();
+    let table = CostTable::new(&expr, max_distance)
+        .map_err(|d| format!("cost is undefined or too big for distance {}", d))?;
+    let cost_fn = |d| table.cost(d);
+    if table.is_convex() {
+        let (position, fuel) = best_position(crabs, cost_fn);
+        Ok((position, fuel, Strategy::Search))
+    } else {
+        let (position, fuel) = brute_force(crabs, cost_fn);
+        Ok((position, fuel, Strategy::BruteForce))
+    }
 }
 
 fn parse_positions(s: &str) -> Vec<i32> {
-    s.split(',').map(|s| s.parse().unwrap()).collect()
+    s.trim().split(',').map(|s| s.parse().unwrap()).collect()
 }
 
-fn best_position<F>(crabs: &[i32], cost_fn: F) -> i32
+/// How much fuel do all the crabs need to move to the target position?
+fn total_cost<F>(crabs: &[i32], target_position: i32, cost_fn: &F) -> i64
 where
-    F: Fn(i32) -> i32 + Clone,
+    F: Fn(i32) -> i32,
 {
+    crabs
+        .iter()
+        .map(|position| {
+            let distance = (target_position - position).abs();
+            cost_fn(distance) as i64
+        })
+        .sum()
+}
+
+/// Find the position where the crabs can line up using the least fuel, and how much fuel that
+/// takes. If several positions are equally good, returns the leftmost.
+///
+/// The cost function must be convex and nondecreasing, i.e. each extra step costs at least as
+/// much as the step before. Then the total cost is convex too, so it only goes down and then up,
+/// and a binary search for where it stops going down finds the minimum.
+fn best_position<F>(crabs: &[i32], cost_fn: F) -> (i32, i64)
+where
+    F: Fn(i32) -> i32,
+{
+    let mut low = *crabs.iter().min().unwrap();
+    let mut high = *crabs.iter().max().unwrap();
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if total_cost(crabs, mid, &cost_fn) <= total_cost(crabs, mid + 1, &cost_fn) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    (low, total_cost(crabs, low, &cost_fn))
+}
+
+/// Try every position between the leftmost and rightmost crab. Slow, but works for any cost.
+/// If several positions are equally good, returns the leftmost.
+fn brute_force<F>(crabs: &[i32], cost_fn: F) -> (i32, i64)
+where
+    F: Fn(i32) -> i32,
+{
+    let min_position = *crabs.iter().min().unwrap();
     let max_position = *crabs.iter().max().unwrap();
-    (0..max_position)
+    (min_position..=max_position)
         .map(|target_position| {
-            crabs
-                .iter()
-                .map(|position| {
-                    let distance = (target_position - position).abs();
-                    cost_fn(distance)
-                })
-                .sum()
+            (
+                target_position,
+                total_cost(crabs, target_position, &cost_fn),
+            )
         })
-        .min()
+        .min_by_key(|&(position, cost)| (cost, position))
+        .unwrap()
+}
+
+/// The best position when each step costs 1 fuel. Moving away from the median always moves
+/// towards fewer crabs than it moves away from, so the median is best.
+fn median_position(crabs: &[i32]) -> (i32, i64) {
+    let mut sorted = crabs.to_vec();
+    sorted.sort_unstable();
+    // With an even number of crabs, anywhere between the two middle crabs is equally good.
+    let median = sorted[(sorted.len() - 1) / 2];
+    (median, total_cost(crabs, median, &|n| n))
+}
+
+/// The best position when each step costs 1 more fuel than the last. The total cost is nearly
+/// quadratic, so the best position is always within half a step of the mean, and only the
+/// positions around it need checking.
+fn mean_position(crabs: &[i32]) -> (i32, i64) {
+    let sum: i64 = crabs.iter().map(|&c| c as i64).sum();
+    let mean = sum.div_euclid(crabs.len() as i64) as i32;
+    (mean - 1..=mean + 2)
+        .map(|position| (position, total_cost(crabs, position, &triangle_num)))
+        .min_by_key(|&(position, cost)| (cost, position))
         .unwrap()
 }
 
@@ -34,11 +157,13 @@ fn triangle_num(n: i32) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rng::Rng;
 
     #[test]
     fn test_q1() {
         let crabs = parse_positions(include_str!("example.txt"));
-        assert_eq!(best_position(&crabs, |n| n), 37)
+        assert_eq!(best_position(&crabs, |n| n), (2, 37));
+        assert_eq!(median_position(&crabs), (2, 37));
     }
 
     #[test]
@@ -51,6 +176,77 @@ mod tests {
     #[test]
     fn test_q2() {
         let crabs = parse_positions(include_str!("example.txt"));
-        assert_eq!(best_position(&crabs, triangle_num), 168)
+        assert_eq!(best_position(&crabs, triangle_num), (5, 168));
+        assert_eq!(mean_position(&crabs), (5, 168));
+    }
+
+    #[test]
+    fn test_edges_and_negatives() {
+        // The old search stopped one short of the rightmost crab, and never went below 0.
+        assert_eq!(best_position(&[7, 9, 9], |n| n), (9, 2));
+        assert_eq!(median_position(&[7, 9, 9]), (9, 2));
+        assert_eq!(best_position(&[-5, -3, -1], triangle_num), (-3, 6));
+        assert_eq!(mean_position(&[-5, -3, -1]), (-3, 6));
+        assert_eq!(mean_position(&[4]), (4, 0));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        type CostFn = fn(i32) -> i32;
+        let costs: [(&str, CostFn); 3] = [
+            ("linear", |n| n),
+            ("triangle", triangle_num),
+            ("square", |n| n * n),
+        ];
+        let mut rng = Rng::new(2021);
+        for _ in 0..50 {
+            // Between 1 and 20 crabs, from -30 to 30.
+            let crabs: Vec<_> = (0..1 + rng.below(20))
+                .map(|_| rng.below(61) as i32 - 30)
+                .collect();
+            for (name, cost_fn) in costs {
+                assert_eq!(
+                    best_position(&crabs, cost_fn),
+                    brute_force(&crabs, cost_fn),
+                    "{} cost for {:?}",
+                    name,
+                    crabs
+                );
+            }
+            assert_eq!(median_position(&crabs), brute_force(&crabs, |n| n));
+            assert_eq!(mean_position(&crabs), brute_force(&crabs, triangle_num));
+        }
+    }
+
+    #[test]
+    fn test_optimise() {
+        let crabs = parse_positions(include_str!("example.txt"));
+        assert_eq!(optimise(&crabs, "d"), Ok((2, 37, Strategy::Search)));
+        assert_eq!(
+            optimise(&crabs, "d * (d + 1) / 2"),
+            Ok((5, 168, Strategy::Search))
+        );
+        for (text, cost_fn) in [
+            ("min(d, 3) + 1", (|d: i32| d.min(3) + 1) as fn(i32) -> i32),
+            ("10 - d", |d| 10 - d),
+        ] {
+            let (position, fuel) = brute_force(&crabs, cost_fn);
+            assert_eq!(
+                optimise(&crabs, text),
+                Ok((position, fuel, Strategy::BruteForce))
+            );
+        }
+        assert!(optimise(&crabs, "d +").is_err());
+        assert_eq!(
+            optimise(&crabs, "1 / (d - 7)"),
+            Err("cost is undefined or too big for distance 7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let crabs = parse_positions(include_str!("input.txt"));
+        assert_eq!(median_position(&crabs), brute_force(&crabs, |n| n));
+        assert_eq!(mean_position(&crabs), brute_force(&crabs, triangle_num));
     }
 }
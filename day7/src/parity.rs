@@ -0,0 +1,80 @@
+//! Checks this solution against the Haskell one in `day7_haskell`, using golden outputs.
+//!
+//! Each fixture in `fixtures/parity` is a `.in` file of crab positions, and a `.out` file with
+//! what `day7.hs` prints for it: the least fuel for Q1, then for Q2. The golden outputs come
+//! from running the Haskell, so they aren't made here: with GHC installed, run
+//! `day7_haskell/capture.sh` to capture them, then `cargo test -- --ignored test_matches_haskell`.
+//! Capture them again whenever the Haskell or the fixtures change.
+//!
+//! The Haskell only tries positions from 0 up to one before the rightmost crab, so fixtures are
+//! generated where the best position is in that range, and both solutions should agree.
+use crate::rng::Rng;
+use crate::{best_position, mean_position, median_position, parse_positions, triangle_num};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/parity")
+}
+
+/// Every fixture's name, input and golden output, sorted by name.
+fn fixtures() -> Vec<(String, String, String)> {
+    let mut fixtures: Vec<_> = fs::read_dir(fixtures_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "in"))
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let input = fs::read_to_string(&path).unwrap();
+            let output = fs::read_to_string(path.with_extension("out"))
+                .unwrap_or_else(|_| panic!("{} has no golden output, run capture.sh", name));
+            (name, input, output)
+        })
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+#[test]
+#[ignore = "needs golden outputs captured with day7_haskell/capture.sh"]
+fn test_matches_haskell() {
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty());
+    for (name, input, output) in fixtures {
+        let crabs = parse_positions(&input);
+        let expected: Vec<i64> = output.lines().map(|l| l.parse().unwrap()).collect();
+        let (_, q1) = median_position(&crabs);
+        let (_, q2) = mean_position(&crabs);
+        assert_eq!(vec![q1, q2], expected, "{}", name);
+        assert_eq!(best_position(&crabs, |n| n).1, q1, "{}", name);
+        assert_eq!(best_position(&crabs, triangle_num).1, q2, "{}", name);
+    }
+}
+
+/// Is the best position somewhere the Haskell would look?
+fn in_haskell_range(crabs: &[i32]) -> bool {
+    let max = *crabs.iter().max().unwrap();
+    let in_range = |(position, _): (i32, i64)| 0 <= position && position < max;
+    in_range(median_position(crabs)) && in_range(mean_position(crabs))
+}
+
+/// Write new random fixture inputs. Their golden outputs need capturing with `capture.sh`.
+/// Run with `cargo test -- --ignored generate_parity_inputs`.
+#[test]
+#[ignore]
+fn generate_parity_inputs() {
+    let mut rng = Rng::new(2021);
+    let mut written = 0;
+    while written < 20 {
+        let n = 2 + rng.below(200);
+        let size = 1 + rng.below(2000);
+        let crabs: Vec<_> = (0..n).map(|_| rng.below(size) as i32).collect();
+        if !in_haskell_range(&crabs) {
+            continue;
+        }
+        let input: Vec<_> = crabs.iter().map(|c| c.to_string()).collect();
+        let path = fixtures_dir().join(format!("random{:02}.in", written));
+        fs::write(path, input.join(",") + "\n").unwrap();
+        written += 1;
+    }
+}
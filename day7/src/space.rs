@@ -0,0 +1,490 @@
+//! Crabs which can move in more than one dimension.
+use std::fmt;
+use std::str::FromStr;
+
+/// A crab at a point in N-dimensional space. Moving a crab with weight `w` costs `w` times as much
+/// fuel as moving a crab with weight 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crab<const N: usize> {
+    pub position: [i32; N],
+    pub weight: u32,
+}
+
+/// How far apart two points are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// Moving along one axis at a time.
+    Manhattan,
+    /// Moving along every axis at once, like a chess king.
+    Chebyshev,
+    /// Moving in a straight line.
+    Euclidean,
+}
+
+impl Metric {
+    pub fn distance<const N: usize>(self, a: &[i32; N], b: &[i32; N]) -> f64 {
+        let deltas = a.iter().zip(b).map(|(a, b)| (*a as i64 - *b as i64).abs());
+        match self {
+            Metric::Manhattan => deltas.sum::<i64>() as f64,
+            Metric::Chebyshev => deltas.max().unwrap_or(0) as f64,
+            Metric::Euclidean => (deltas.map(|d| d * d).sum::<i64>() as f64).sqrt(),
+        }
+    }
+}
+
+/// How much fuel do all the crabs need to meet at the point?
+pub fn total_cost<const N: usize, F>(
+    crabs: &[Crab<N>],
+    metric: Metric,
+    point: &[i32; N],
+    cost_fn: &F,
+) -> f64
+where
+    F: Fn(f64) -> f64,
+{
+    crabs
+        .iter()
+        .map(|crab| crab.weight as f64 * cost_fn(metric.distance(&crab.position, point)))
+        .sum()
+}
+
+/// Find the point where the crabs can meet using the least fuel, and how much fuel that takes.
+/// Meeting points are always on the grid, inside the box around all the crabs.
+///
+/// Like `best_position`, the cost function must be convex and nondecreasing. Every metric is
+/// convex too, so along any one axis the total cost only goes down and then up, and a binary
+/// search along the last axis finds the best point on each line. With Manhattan or Chebyshev
+/// distance, the best cost on each plane through the box goes down and then up as well, so the
+/// other axes are binary searched too, making O(2^N * log(width)^N) evaluations of the total
+/// cost.
+///
+/// That isn't true with Euclidean distance once points have to be on the grid. Instead, the best
+/// point anywhere in the box is found first, then the search walks from the nearest grid point to
+/// cheaper neighbours until there aren't any. That also takes O(log(width)^N) evaluations, plus a
+/// few for the walk, but it's only a local minimum: the grid can have a cheaper point which
+/// isn't next to it.
+pub fn meeting_point<const N: usize, F>(
+    crabs: &[Crab<N>],
+    metric: Metric,
+    cost_fn: F,
+) -> ([i32; N], f64)
+where
+    F: Fn(f64) -> f64,
+{
+    let bounds = bounds(crabs);
+    if metric == Metric::Euclidean {
+        let mut point = [0.0; N];
+        continuous_search(crabs, &cost_fn, &bounds, &mut point, 0);
+        return descend(
+            crabs,
+            metric,
+            &cost_fn,
+            &bounds,
+            point.map(|x| x.round() as i32),
+        );
+    }
+    let mut point = [0; N];
+    let cost = search(crabs, metric, &cost_fn, &bounds, &mut point, 0);
+    (point, cost)
+}
+
+/// Find the best coordinates along this axis and all the later ones, with the earlier axes
+/// fixed. Leaves them in `point`, and returns their cost.
+fn search<const N: usize, F>(
+    crabs: &[Crab<N>],
+    metric: Metric,
+    cost_fn: &F,
+    bounds: &[(i32, i32); N],
+    point: &mut [i32; N],
+    axis: usize,
+) -> f64
+where
+    F: Fn(f64) -> f64,
+{
+    if axis == N {
+        return total_cost(crabs, metric, point, cost_fn);
+    }
+    // The best cost for each coordinate goes down and then up, so search for the lowest.
+    let (mut low, mut high) = bounds[axis];
+    let mut cost_at = |x: i32| {
+        point[axis] = x;
+        search(crabs, metric, cost_fn, bounds, point, axis + 1)
+    };
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if cost_at(mid) <= cost_at(mid + 1) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    cost_at(low)
+}
+
+/// Like `search`, but with Euclidean distance and points anywhere in the box, not just on the
+/// grid. Off the grid, the best cost on each plane does go down and then up, so a golden-section
+/// search on each axis finds the best coordinate to within a tenth.
+fn continuous_search<const N: usize, F>(
+    crabs: &[Crab<N>],
+    cost_fn: &F,
+    bounds: &[(i32, i32); N],
+    point: &mut [f64; N],
+    axis: usize,
+) -> f64
+where
+    F: Fn(f64) -> f64,
+{
+    if axis == N {
+        return crabs
+            .iter()
+            .map(|crab| {
+                let squares = crab.position.iter().zip(point.iter()).map(|(&a, b)| {
+                    let delta = a as f64 - b;
+                    delta * delta
+                });
+                crab.weight as f64 * cost_fn(squares.sum::<f64>().sqrt())
+            })
+            .sum();
+    }
+    let ratio = (5_f64.sqrt() - 1.0) / 2.0;
+    let (low, high) = bounds[axis];
+    let (mut low, mut high) = (low as f64, high as f64);
+    let mut cost_at = |x: f64| {
+        point[axis] = x;
+        continuous_search(crabs, cost_fn, bounds, point, axis + 1)
+    };
+    // Keep two points inside the range, and narrow it past whichever one costs more.
+    let (mut a, mut b) = (high - ratio * (high - low), low + ratio * (high - low));
+    let (mut cost_a, mut cost_b) = (cost_at(a), cost_at(b));
+    while high - low > 0.1 {
+        if cost_a <= cost_b {
+            (high, b, cost_b) = (b, a, cost_a);
+            a = high - ratio * (high - low);
+            cost_a = cost_at(a);
+        } else {
+            (low, a, cost_a) = (a, b, cost_b);
+            b = low + ratio * (high - low);
+            cost_b = cost_at(b);
+        }
+    }
+    cost_at((low + high) / 2.0)
+}
+
+/// Starting from this point, keep moving to the cheapest neighbouring point in the box,
+/// diagonals included, until none of them are cheaper. Returns where it stops, and its cost.
+fn descend<const N: usize, F>(
+    crabs: &[Crab<N>],
+    metric: Metric,
+    cost_fn: &F,
+    bounds: &[(i32, i32); N],
+    start: [i32; N],
+) -> ([i32; N], f64)
+where
+    F: Fn(f64) -> f64,
+{
+    let mut best = (start, total_cost(crabs, metric, &start, cost_fn));
+    loop {
+        let point = best.0;
+        for step in 0..3_usize.pow(N as u32) {
+            let neighbour: [i32; N] = std::array::from_fn(|axis| {
+                point[axis] + (step / 3_usize.pow(axis as u32) % 3) as i32 - 1
+            });
+            let inside = (0..N).all(|axis| {
+                let (low, high) = bounds[axis];
+                (low..=high).contains(&neighbour[axis])
+            });
+            if inside {
+                let cost = total_cost(crabs, metric, &neighbour, cost_fn);
+                if cost < best.1 {
+                    best = (neighbour, cost);
+                }
+            }
+        }
+        if best.0 == point {
+            return best;
+        }
+    }
+}
+
+/// The smallest and largest coordinate of any crab, along each axis.
+fn bounds<const N: usize>(crabs: &[Crab<N>]) -> [(i32, i32); N] {
+    std::array::from_fn(|axis| {
+        let coordinates = crabs.iter().map(|crab| crab.position[axis]);
+        (
+            coordinates.clone().min().unwrap(),
+            coordinates.max().unwrap(),
+        )
+    })
+}
+
+/// Why a crab couldn't be parsed. Lines are numbered from 1.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    WrongDimensions { line: usize, expected: usize },
+    BadNumber { line: usize, value: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::WrongDimensions { line, expected } => {
+                write!(f, "line {}: expected {} coordinates", line, expected)
+            }
+            ParseError::BadNumber { line, value } => {
+                write!(f, "line {}: {} isn't a number", line, value)
+            }
+        }
+    }
+}
+
+/// Parse one crab per line, like `3,-1,4` for a crab in 3D, or `3,-1,4*2` for one with weight 2.
+pub fn parse_crabs<const N: usize>(s: &str) -> Result<Vec<Crab<N>>, ParseError> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(i, text)| {
+            let line = i + 1;
+            let (coordinates, weight) = text.trim().split_once('*').unwrap_or((text.trim(), "1"));
+            let coordinates = coordinates
+                .split(',')
+                .map(|value| parse_number(line, value))
+                .collect::<Result<Vec<_>, _>>()?;
+            let position = coordinates
+                .try_into()
+                .map_err(|_| ParseError::WrongDimensions { line, expected: N })?;
+            let weight = parse_number(line, weight)?;
+            Ok(Crab { position, weight })
+        })
+        .collect()
+}
+
+fn parse_number<T: FromStr>(line: usize, value: &str) -> Result<T, ParseError> {
+    value.trim().parse().map_err(|_| ParseError::BadNumber {
+        line,
+        value: value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    /// Try every point in the box around the crabs.
+    fn brute_force<const N: usize, F>(crabs: &[Crab<N>], metric: Metric, cost_fn: F) -> f64
+    where
+        F: Fn(f64) -> f64,
+    {
+        let bounds = bounds(crabs);
+        let mut points = vec![[0; N]];
+        for (axis, &(low, high)) in bounds.iter().enumerate() {
+            points = points
+                .into_iter()
+                .flat_map(|point| {
+                    (low..=high).map(move |x| {
+                        let mut point = point;
+                        point[axis] = x;
+                        point
+                    })
+                })
+                .collect();
+        }
+        points
+            .iter()
+            .map(|point| total_cost(crabs, metric, point, &cost_fn))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Generate crabs at random positions in a box `size` wide around the origin, with random
+    /// weights.
+    fn random_crabs<const N: usize>(rng: &mut Rng, n: usize, size: i32) -> Vec<Crab<N>> {
+        (0..n)
+            .map(|_| Crab {
+                position: std::array::from_fn(|_| rng.below(size as usize) as i32 - size / 2),
+                weight: 1 + rng.below(3) as u32,
+            })
+            .collect()
+    }
+
+    const METRICS: [Metric; 3] = [Metric::Manhattan, Metric::Chebyshev, Metric::Euclidean];
+
+    #[test]
+    fn test_distance() {
+        let (a, b) = ([1, 2, 3], [4, -2, 3]);
+        assert_eq!(Metric::Manhattan.distance(&a, &b), 7.0);
+        assert_eq!(Metric::Chebyshev.distance(&a, &b), 4.0);
+        assert_eq!(Metric::Euclidean.distance(&a, &b), 5.0);
+    }
+
+    #[test]
+    fn test_weights() {
+        // The heavy crab shouldn't move.
+        let crabs = parse_crabs::<2>("0,0\n10,10*5\n0,10").unwrap();
+        for metric in METRICS {
+            assert_eq!(
+                meeting_point(&crabs, metric, |d| d).0,
+                [10, 10],
+                "{:?}",
+                metric
+            );
+        }
+    }
+
+    #[test]
+    fn test_euclidean_on_grid() {
+        // The best point on each column wanders, so a search over columns would get stuck
+        // around (-5,3).
+        let crabs = parse_crabs::<2>("2,2\n-8,4*3\n8,-2*2").unwrap();
+        assert_eq!(meeting_point(&crabs, Metric::Euclidean, |d| d).0, [-8, 4]);
+    }
+
+    /// Is the point at least as cheap as every neighbouring point in the box, diagonals included?
+    fn is_local_minimum<const N: usize, F>(
+        crabs: &[Crab<N>],
+        metric: Metric,
+        cost_fn: F,
+        point: [i32; N],
+    ) -> bool
+    where
+        F: Fn(f64) -> f64,
+    {
+        let bounds = bounds(crabs);
+        let cost = total_cost(crabs, metric, &point, &cost_fn);
+        let mut neighbours = vec![point];
+        for axis in 0..N {
+            neighbours = neighbours
+                .into_iter()
+                .flat_map(|p| {
+                    (-1..=1).map(move |delta| {
+                        let mut p = p;
+                        p[axis] += delta;
+                        p
+                    })
+                })
+                .filter(|p| (bounds[axis].0..=bounds[axis].1).contains(&p[axis]))
+                .collect();
+        }
+        neighbours
+            .iter()
+            .all(|p| total_cost(crabs, metric, p, &cost_fn) >= cost)
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let costs: [fn(f64) -> f64; 2] = [|d| d, |d| d * (d + 1.0) / 2.0];
+        let mut rng = Rng::new(2021);
+        for _ in 0..30 {
+            let n = rng.below(10);
+            let flat = random_crabs::<2>(&mut rng, 1 + n, 20);
+            let deep = random_crabs::<3>(&mut rng, 1 + n % 6, 10);
+            for metric in [Metric::Manhattan, Metric::Chebyshev] {
+                for cost_fn in costs {
+                    let (_, cost) = meeting_point(&flat, metric, cost_fn);
+                    let expected = brute_force(&flat, metric, cost_fn);
+                    assert!((cost - expected).abs() < 1e-6, "{:?} {:?}", metric, flat);
+                    let (_, cost) = meeting_point(&deep, metric, cost_fn);
+                    let expected = brute_force(&deep, metric, cost_fn);
+                    assert!((cost - expected).abs() < 1e-6, "{:?} {:?}", metric, deep);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_euclidean_is_local() {
+        // Euclidean meeting points are only local minima, but they're close to the best.
+        let costs: [fn(f64) -> f64; 2] = [|d| d, |d| d * (d + 1.0) / 2.0];
+        let mut rng = Rng::new(2021);
+        for _ in 0..30 {
+            let n = rng.below(10);
+            let flat = random_crabs::<2>(&mut rng, 1 + n, 20);
+            let deep = random_crabs::<3>(&mut rng, 1 + n % 6, 10);
+            for cost_fn in costs {
+                let (point, cost) = meeting_point(&flat, Metric::Euclidean, cost_fn);
+                assert!(is_local_minimum(&flat, Metric::Euclidean, cost_fn, point));
+                let expected = brute_force(&flat, Metric::Euclidean, cost_fn);
+                assert!(cost <= expected * 1.01, "{:?}", flat);
+                let (point, cost) = meeting_point(&deep, Metric::Euclidean, cost_fn);
+                assert!(is_local_minimum(&deep, Metric::Euclidean, cost_fn, point));
+                let expected = brute_force(&deep, Metric::Euclidean, cost_fn);
+                assert!(cost <= expected * 1.01, "{:?}", deep);
+            }
+        }
+        // Two crabs are equally happy anywhere on the line between them, but the only grid
+        // points on it are the crabs themselves, and the walk stops just off the line.
+        let crabs = parse_crabs::<2>("-3,-2\n-1,3").unwrap();
+        let (point, cost) = meeting_point(&crabs, Metric::Euclidean, |d| d);
+        assert!(cost > brute_force(&crabs, Metric::Euclidean, |d| d));
+        assert!(is_local_minimum(&crabs, Metric::Euclidean, |d| d, point));
+    }
+
+    #[test]
+    fn test_puzzle_sized_3d() {
+        // With Manhattan distance and a cost of 1 per step, each axis is independent, and the
+        // best coordinate on each is the median.
+        let crabs: Vec<Crab<3>> = random_crabs(&mut Rng::new(2021), 1000, 2000)
+            .into_iter()
+            .map(|crab| Crab { weight: 1, ..crab })
+            .collect();
+        let median_cost: f64 = (0..3)
+            .map(|axis| {
+                let mut coordinates: Vec<_> = crabs.iter().map(|c| c.position[axis]).collect();
+                coordinates.sort_unstable();
+                let median = coordinates[coordinates.len() / 2];
+                coordinates
+                    .iter()
+                    .map(|x| (x - median).abs() as f64)
+                    .sum::<f64>()
+            })
+            .sum();
+        let (_, cost) = meeting_point(&crabs, Metric::Manhattan, |d| d);
+        assert_eq!(cost, median_cost);
+        // Chebyshev and Euclidean have no shortcut, but the point should beat all its neighbours.
+        for metric in [Metric::Chebyshev, Metric::Euclidean] {
+            let (point, _) = meeting_point(&crabs, metric, |d| d);
+            assert!(
+                is_local_minimum(&crabs, metric, |d| d, point),
+                "{:?}",
+                metric
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_crabs() {
+        assert_eq!(
+            parse_crabs::<3>("1,2,3\n-4,5,6*3\n"),
+            Ok(vec![
+                Crab {
+                    position: [1, 2, 3],
+                    weight: 1
+                },
+                Crab {
+                    position: [-4, 5, 6],
+                    weight: 3
+                },
+            ])
+        );
+        assert_eq!(
+            parse_crabs::<2>("1,2\n1,2,3"),
+            Err(ParseError::WrongDimensions {
+                line: 2,
+                expected: 2
+            })
+        );
+        assert_eq!(
+            parse_crabs::<1>("1*-2"),
+            Err(ParseError::BadNumber {
+                line: 1,
+                value: "-2".to_string()
+            })
+        );
+        assert_eq!(
+            parse_crabs::<2>("1,x"),
+            Err(ParseError::BadNumber {
+                line: 1,
+                value: "x".to_string()
+            })
+        );
+    }
+}
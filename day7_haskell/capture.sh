@@ -0,0 +1,9 @@
+#!/bin/sh
+# Record this solution's output for every parity fixture, as the golden output the Rust solution
+# is tested against. Run from anywhere, with GHC installed.
+set -e
+cd "$(dirname "$0")"
+for input in ../day7/fixtures/parity/*.in; do
+    runghc day7.hs "$input" > "${input%.in}.out"
+    echo "captured ${input%.in}.out"
+done
@@ -1,6 +1,16 @@
+import System.Environment (getArgs)
+
+-- Reads comma-separated crab positions from the file named by the first argument, or uses
+-- problemInput if there isn't one.
 main = do
-    print $ lowestFuelToAlign problemInput id
-    print $ lowestFuelToAlign problemInput triangle
+    args <- getArgs
+    crabs <- case args of
+        (path:_) -> do
+            input <- readFile path
+            return (read ("[" ++ input ++ "]"))
+        [] -> return problemInput
+    print $ lowestFuelToAlign crabs id
+    print $ lowestFuelToAlign crabs triangle
 
 triangle :: Int -> Int
 triangle n = n * (n+1) `div` 2
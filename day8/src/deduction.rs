@@ -1,106 +1,243 @@
 use crate::parse::{DisplayPanel, Pattern};
-use std::collections::{BTreeMap, BTreeSet};
-
-pub fn solve(display: DisplayPanel) -> usize {
-    // First, figure out which number each signal pattern corresponds to.
-    // Begin by bucketing each pattern according to the number of segments.
-    // Sometimes a number can be uniquely identified from the pattern (i.e. 1/4/7/8),
-    // othertimes there are a few possibilities (e.g. 2, 3 and 5 all have the same number of segments).
-    let mut one = Pattern::default();
-    let mut seven = Pattern::default();
-    let mut four = Pattern::default();
-    let mut two_three_five = BTreeSet::<Pattern>::default();
-    let mut zero_six_nine = BTreeSet::<Pattern>::default();
-    let mut eight = Pattern::default();
-    for pattern in display.signal_patterns {
-        match pattern.len() {
-            2 => one = pattern,
-            3 => seven = pattern,
-            4 => four = pattern,
-            5 => {
-                two_three_five.insert(pattern);
-            }
-            6 => {
-                zero_six_nine.insert(pattern);
+use std::fmt;
+
+/// The symbols a display can show, as the segments each one lights up.
+/// A symbol's value is its position in the list, so e.g. hex digits are 0-9 then A-F.
+pub struct Glyphs(Vec<Pattern>);
+
+impl Glyphs {
+    fn new(glyphs: &[&str]) -> Self {
+        Self(glyphs.iter().map(|g| g.parse().unwrap()).collect())
+    }
+
+    /// The digits 0 to 9, as drawn in the puzzle.
+    pub fn decimal() -> Self {
+        Self::new(&[
+            "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+        ])
+    }
+
+    /// The digits 0 to 9, then A, b, C, d, E and F.
+    pub fn hex() -> Self {
+        let mut glyphs = Self::decimal();
+        glyphs
+            .0
+            .extend(Self::new(&["abcdef", "bdefg", "abeg", "cdefg", "abdeg", "abde"]).0);
+        glyphs
+    }
+
+    pub fn radix(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The segments lit up to show this value.
+    pub fn glyph(&self, value: usize) -> Pattern {
+        self.0[value]
+    }
+}
+
+/// Why a display couldn't be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// No way of connecting the wires to the segments makes every pattern a glyph.
+    NoSolution,
+    /// Several ways of connecting the wires make every pattern a glyph, and they disagree on what
+    /// the output is, e.g. these two readings.
+    Ambiguous(Vec<usize>, Vec<usize>),
+    /// The output has too many digits to fit in a `usize`.
+    TooBig(Vec<usize>),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NoSolution => write!(f, "no wiring fits these patterns"),
+            DecodeError::Ambiguous(a, b) => {
+                write!(f, "the output could be {:?} or {:?}", a, b)
             }
-            7 => eight = pattern,
-            _ => unreachable!(),
+            DecodeError::TooBig(digits) => write!(f, "the output {:?} is too big", digits),
         }
     }
+}
 
-    // Now we can start deducing which number each signal pattern represents.
-    // There are three numbers with five segments (2, 3 and 5).
-    // We can identify 3 because (2 and 5) jointly use all seven segments, but
-    // neither (2 and 3) nor (5 and 3) do.
-    let three = {
-        let mut possibilities = two_three_five.iter();
-        let p = possibilities.next().unwrap();
-        let q = possibilities.next().unwrap();
-        let r = possibilities.next().unwrap();
-        if p.union(q).count() == 7 {
-            r
-        } else if q.union(r).count() == 7 {
-            p
-        } else {
-            q
-        }
-    };
-    // Nine is a superset of four's segments.
-    let nine = zero_six_nine
-        .iter()
-        .find(|pattern| pattern.is_superset(&four))
-        .unwrap();
-    // Five is a subset of nine's segments
-    let five = two_three_five
-        .iter()
-        .find(|p| *p != three && p.is_subset(nine))
-        .unwrap();
-
-    // We now have enough information to fill in the remaining numbers, and assign
-    // each pattern to its actual numeric value.
-    let map = BTreeMap::from([
-        // Zero is a superset of seven, but six is not.
-        (
-            zero_six_nine
-                .iter()
-                .find(|p| p.is_superset(&seven) && *p != nine)
-                .unwrap(),
-            0_usize,
-        ),
-        (&one, 1),
-        // Two is the only unknown five-segment pattern left.
-        (
-            two_three_five
-                .iter()
-                .find(|p| *p != five && *p != three)
-                .unwrap(),
-            2,
-        ),
-        (three, 3),
-        (&four, 4),
-        (five, 5),
-        (
-            zero_six_nine
-                .iter()
-                .find(|p| !p.is_superset(&seven) && *p != nine)
-                .unwrap(),
-            6,
-        ),
-        (&seven, 7),
-        (&eight, 8),
-        (nine, 9),
-    ]);
-
-    // Now that we know which pattern each number has, it's easy to calculate the
-    // four digit output value.
-    display
-        .output_value
+/// Decode the display as a decimal number.
+pub fn solve(display: &DisplayPanel) -> Result<usize, DecodeError> {
+    read_number(display, &Glyphs::decimal())
+}
+
+/// Decode the display as a number, with one digit per output pattern.
+pub fn read_number(display: &DisplayPanel, glyphs: &Glyphs) -> Result<usize, DecodeError> {
+    let digits = decode(&display.signal_patterns, &display.output_value, glyphs)?;
+    digits
         .iter()
-        .enumerate()
-        .map(|(i, digit)| {
-            let n = display.output_value.len();
-            let base = 10_usize.pow((n - 1 - i) as u32);
-            base * map[digit]
+        .try_fold(0usize, |n, &d| {
+            n.checked_mul(glyphs.radix())?.checked_add(d)
         })
-        .sum()
+        .ok_or(DecodeError::TooBig(digits))
+}
+
+/// Work out which wire is connected to which segment, and use it to read each output pattern.
+/// Every signal and output pattern must be one of the glyphs, once the wires are untangled.
+/// Returns the value of each output pattern.
+///
+/// There are only 7! = 5040 ways to connect the wires, but rather than trying all of them, wires
+/// are connected one at a time, backtracking as soon as any pattern can't be a glyph.
+pub fn decode(
+    signal_patterns: &[Pattern],
+    output_value: &[Pattern],
+    glyphs: &Glyphs,
+) -> Result<Vec<usize>, DecodeError> {
+    let patterns: Vec<_> = signal_patterns.iter().chain(output_value).collect();
+    let mut search = Search {
+        patterns: &patterns,
+        glyphs,
+        wiring: Vec::with_capacity(Pattern::SEGMENTS.len()),
+        readings: Vec::new(),
+    };
+    search.connect_next_wire();
+    let mut readings = search.readings.into_iter();
+    let reading = readings.next().ok_or(DecodeError::NoSolution)?;
+    // The patterns which aren't outputs can be read however they like.
+    let outputs = |reading: Vec<usize>| reading[signal_patterns.len()..].to_vec();
+    let output = outputs(reading);
+    for other in readings.map(outputs) {
+        if other != output {
+            return Err(DecodeError::Ambiguous(output, other));
+        }
+    }
+    Ok(output)
+}
+
+/// A backtracking search for every wiring which makes every pattern a glyph.
+struct Search<'a> {
+    patterns: &'a [&'a Pattern],
+    glyphs: &'a Glyphs,
+    /// `wiring[i]` is the segment the `i`th wire is connected to.
+    wiring: Vec<Pattern>,
+    /// The value of each pattern, for each complete wiring found so far.
+    readings: Vec<Vec<usize>>,
+}
+
+impl Search<'_> {
+    fn connect_next_wire(&mut self) {
+        if self.wiring.len() == Pattern::SEGMENTS.len() {
+            let reading = self
+                .patterns
+                .iter()
+                .map(|p| self.read(p).unwrap())
+                .collect();
+            self.readings.push(reading);
+            return;
+        }
+        // Wires are connected in order, from `a` to `g`.
+        for segment in Pattern::SEGMENTS {
+            if self.wiring.contains(&segment) {
+                continue;
+            }
+            self.wiring.push(segment);
+            if self.patterns.iter().all(|p| self.could_be_glyph(p)) {
+                self.connect_next_wire();
+            }
+            self.wiring.pop();
+        }
+    }
+
+    /// Follow the wires connected so far. Returns the segments the pattern lights up, and every
+    /// segment with a wire connected to it.
+    fn follow(&self, pattern: &Pattern) -> (Pattern, Pattern) {
+        let (mut lit, mut connected) = (Pattern::default(), Pattern::default());
+        for (wire, &segment) in Pattern::SEGMENTS.iter().zip(&self.wiring) {
+            connected = connected.union(segment);
+            if wire.is_subset(*pattern) {
+                lit = lit.union(segment);
+            }
+        }
+        (lit, connected)
+    }
+
+    /// With the wires connected so far, is there a glyph this pattern could still be?
+    fn could_be_glyph(&self, pattern: &Pattern) -> bool {
+        let (lit, connected) = self.follow(pattern);
+        self.glyphs
+            .0
+            .iter()
+            .any(|glyph| glyph.len() == pattern.len() && glyph.intersection(connected) == lit)
+    }
+
+    /// Which glyph is this pattern, now that every wire is connected?
+    fn read(&self, pattern: &Pattern) -> Option<usize> {
+        let (lit, _) = self.follow(pattern);
+        self.glyphs.0.iter().position(|glyph| *glyph == lit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode::{encode, Wiring};
+    use crate::rng::Rng;
+    use std::time::Instant;
+
+    fn patterns(s: &str) -> Vec<Pattern> {
+        s.split_whitespace().map(|p| p.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let expected = [8394, 9781, 1197, 9361, 4873, 8418, 4548, 1625, 8717, 4315];
+        let panels = DisplayPanel::parse_lines(include_str!("example.txt"))
+            .unwrap()
+            .1;
+        let actual: Vec<_> = panels.iter().map(|p| solve(p).unwrap()).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        // Two different patterns can't both be 1.
+        let result = decode(&patterns("ab cd"), &patterns("ab"), &Glyphs::decimal());
+        assert_eq!(result, Err(DecodeError::NoSolution));
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        // Without any signal patterns, five segments could be 2, 3 or 5.
+        let result = decode(&[], &patterns("abcde"), &Glyphs::decimal());
+        assert!(matches!(result, Err(DecodeError::Ambiguous(..))));
+        // But the output only needs to be unambiguous, not the wiring.
+        let result = decode(&[], &patterns("ab gfedcba"), &Glyphs::decimal());
+        assert_eq!(result, Ok(vec![1, 8]));
+    }
+
+    #[test]
+    fn test_hex() {
+        let glyphs = Glyphs::hex();
+        let wiring: Wiring = "dgbeacf".parse().unwrap();
+        let panel = encode(0xC0FFEE, 6, &wiring, &glyphs);
+        assert_eq!(read_number(&panel, &glyphs), Ok(0xC0FFEE));
+        // Hex glyphs on a decimal display don't make sense.
+        assert_eq!(solve(&panel), Err(DecodeError::NoSolution));
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_decode() {
+        let mut rng = Rng::new(2021);
+        let glyphs = Glyphs::decimal();
+        let panels: Vec<_> = (0..20_000)
+            .map(|_| {
+                let number = rng.below(10_000);
+                (
+                    encode(number, 4, &Wiring::random(&mut rng), &glyphs),
+                    number,
+                )
+            })
+            .collect();
+        let start = Instant::now();
+        for (panel, expected) in &panels {
+            assert_eq!(solve(panel), Ok(*expected));
+        }
+        println!("Decoded {} panels in {:?}", panels.len(), start.elapsed());
+    }
 }
@@ -0,0 +1,168 @@
+//! Showing numbers on displays with tangled wires: the opposite of `deduction`.
+use crate::deduction::Glyphs;
+use crate::parse::{DisplayPanel, Pattern};
+use std::fmt;
+use std::str::FromStr;
+
+/// Which wire is connected to each segment. Written as seven letters, e.g. `dgbeacf` means wire
+/// `d` lights segment `a`, wire `g` lights segment `b`, and so on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wiring([Pattern; 7]);
+
+impl Wiring {
+    /// The pattern of wires which lights up these segments.
+    pub fn scramble(&self, segments: Pattern) -> Pattern {
+        Pattern::SEGMENTS
+            .iter()
+            .zip(self.0)
+            .filter(|(segment, _)| segment.is_subset(segments))
+            .map(|(_, wire)| wire)
+            .collect()
+    }
+
+    /// Connect the wires to the segments at random.
+    #[cfg(test)]
+    pub fn random(rng: &mut crate::rng::Rng) -> Self {
+        let mut wires = Pattern::SEGMENTS;
+        rng.shuffle(&mut wires);
+        Self(wires)
+    }
+}
+
+/// Why a wiring couldn't be parsed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum WiringError {
+    WrongLength(usize),
+    NotAWire(char),
+    Repeated(char),
+}
+
+impl fmt::Display for WiringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WiringError::WrongLength(n) => write!(f, "expected 7 wires, found {}", n),
+            WiringError::NotAWire(c) => write!(f, "{:?} isn't a wire, they're a to g", c),
+            WiringError::Repeated(c) => write!(f, "wire {} is connected twice", c),
+        }
+    }
+}
+
+impl FromStr for Wiring {
+    type Err = WiringError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut wires = Vec::new();
+        for c in s.chars() {
+            let wire = Pattern::try_from(c).map_err(WiringError::NotAWire)?;
+            if wires.contains(&wire) {
+                return Err(WiringError::Repeated(c));
+            }
+            wires.push(wire);
+        }
+        let n = wires.len();
+        Ok(Self(
+            wires.try_into().map_err(|_| WiringError::WrongLength(n))?,
+        ))
+    }
+}
+
+/// Show the number on a display with `width` digits, padded with leading zeros, or more digits if
+/// it doesn't fit. The signal patterns are every glyph, sorted so their order gives nothing away.
+pub fn encode(number: usize, width: usize, wiring: &Wiring, glyphs: &Glyphs) -> DisplayPanel {
+    let mut digits = Vec::new();
+    let mut rest = number;
+    while rest > 0 || digits.len() < width.max(1) {
+        digits.push(rest % glyphs.radix());
+        rest /= glyphs.radix();
+    }
+    let mut signal_patterns: Vec<_> = (0..glyphs.radix())
+        .map(|value| wiring.scramble(glyphs.glyph(value)))
+        .collect();
+    signal_patterns.sort();
+    let output_value = digits
+        .iter()
+        .rev()
+        .map(|&d| wiring.scramble(glyphs.glyph(d)))
+        .collect();
+    DisplayPanel {
+        signal_patterns,
+        output_value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deduction::{read_number, solve, DecodeError};
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_parse_wiring() {
+        let wire = |c| Pattern::try_from(c).unwrap();
+        assert_eq!(
+            "dgbeacf".parse(),
+            Ok(Wiring(['d', 'g', 'b', 'e', 'a', 'c', 'f'].map(wire)))
+        );
+        assert_eq!("abc".parse::<Wiring>(), Err(WiringError::WrongLength(3)));
+        assert_eq!("abcdefh".parse::<Wiring>(), Err(WiringError::NotAWire('h')));
+        assert_eq!("abcdefa".parse::<Wiring>(), Err(WiringError::Repeated('a')));
+    }
+
+    #[test]
+    fn test_encode_example() {
+        // The wiring from the puzzle's worked example.
+        let wiring: Wiring = "deafgbc".parse().unwrap();
+        let panel = encode(5353, 4, &wiring, &Glyphs::decimal());
+        let mut expected = DisplayPanel::parse_lines(include_str!("tiny.txt"))
+            .unwrap()
+            .1
+            .pop()
+            .unwrap();
+        expected.signal_patterns.sort();
+        assert_eq!(panel, expected);
+    }
+
+    #[test]
+    fn test_width() {
+        let wiring: Wiring = "abcdefg".parse().unwrap();
+        let glyphs = Glyphs::decimal();
+        assert_eq!(
+            encode(7, 4, &wiring, &glyphs)
+                .to_string()
+                .split(" | ")
+                .nth(1),
+            Some("abcefg abcefg abcefg acf")
+        );
+        assert_eq!(encode(0, 0, &wiring, &glyphs).output_value.len(), 1);
+        assert_eq!(encode(123456, 4, &wiring, &glyphs).output_value.len(), 6);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = Rng::new(2021);
+        for _ in 0..200 {
+            let wiring = Wiring::random(&mut rng);
+            // Pick the number of bits first, so there are plenty of short numbers too.
+            let bits = rng.below(40);
+            let number = rng.below(1 << bits);
+            let width = rng.below(12);
+            for glyphs in [Glyphs::decimal(), Glyphs::hex()] {
+                let line = encode(number, width, &wiring, &glyphs).to_string();
+                let panel = DisplayPanel::parse_lines(&line).unwrap().1.pop().unwrap();
+                assert_eq!(read_number(&panel, &glyphs), Ok(number), "{}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_too_big() {
+        let wiring: Wiring = "gfedcba".parse().unwrap();
+        let panel = encode(usize::MAX, 0, &wiring, &Glyphs::decimal());
+        assert_eq!(solve(&panel), Ok(usize::MAX));
+        let panel = encode(usize::MAX, 25, &wiring, &Glyphs::decimal());
+        assert_eq!(solve(&panel), Ok(usize::MAX));
+        let mut panel = encode(usize::MAX, 0, &wiring, &Glyphs::decimal());
+        panel.output_value.push(panel.output_value[0]);
+        assert!(matches!(solve(&panel), Err(DecodeError::TooBig(_))));
+    }
+}
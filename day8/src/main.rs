@@ -1,7 +1,11 @@
+use deduction::Glyphs;
+use encode::Wiring;
 use parse::DisplayPanel;
-use std::collections::BTreeSet;
 mod deduction;
+mod encode;
 mod parse;
+#[cfg(test)]
+mod rng;
 
 fn main() {
     let display_panels = DisplayPanel::parse_lines(include_str!("input.txt"))
@@ -9,18 +13,33 @@ fn main() {
         .1;
     let q1 = count_unique_len(&display_panels);
     println!("Q1: {}", q1);
-    let q2: usize = display_panels.into_iter().map(deduction::solve).sum();
+    let mut q2 = 0;
+    for (i, panel) in display_panels.iter().enumerate() {
+        match deduction::solve(panel) {
+            Ok(value) => q2 += value,
+            Err(e) => eprintln!("Can't decode display {}: {}", i, e),
+        }
+    }
     println!("Q2: {}", q2);
+
+    // Tangle the wires of a hex display, and check it can still be read.
+    let glyphs = Glyphs::hex();
+    let wiring: Wiring = "dgbeacf".parse().unwrap();
+    let panel = encode::encode(0xC0FFEE, 8, &wiring, &glyphs);
+    println!("{}", panel);
+    match deduction::read_number(&panel, &glyphs) {
+        Ok(value) => println!("reads as {:#X}", value),
+        Err(e) => eprintln!("Can't decode hex display: {}", e),
+    }
 }
 
 fn count_unique_len(display_panels: &[DisplayPanel]) -> usize {
-    let unique_len: BTreeSet<_> = BTreeSet::from([2, 3, 4, 7]);
     display_panels
         .iter()
         .map(|p| {
             p.output_value
                 .iter()
-                .filter(|segs| unique_len.contains(&segs.len()))
+                .filter(|segs| matches!(segs.len(), 2 | 3 | 4 | 7))
                 .count()
         })
         .sum()
@@ -50,7 +69,7 @@ mod tests {
         let display_panel = display_panels.pop().unwrap();
 
         // Check the answer.
-        let actual = deduction::solve(display_panel);
-        assert_eq!(5353, actual);
+        let actual = deduction::solve(&display_panel);
+        assert_eq!(Ok(5353), actual);
     }
 }
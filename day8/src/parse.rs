@@ -5,35 +5,114 @@ use nom::{
     sequence::separated_pair,
     IResult,
 };
-use std::collections::BTreeSet;
-use std::convert::TryInto;
+use std::fmt;
+use std::str::FromStr;
 
-pub type Pattern = BTreeSet<char>;
+/// A set of segments, as a bitmask where bit 0 is segment `a` and bit 6 is segment `g`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Pattern(u8);
+
+impl Pattern {
+    /// Each segment on its own, from `a` to `g`.
+    pub const SEGMENTS: [Pattern; 7] = [
+        Pattern(1),
+        Pattern(1 << 1),
+        Pattern(1 << 2),
+        Pattern(1 << 3),
+        Pattern(1 << 4),
+        Pattern(1 << 5),
+        Pattern(1 << 6),
+    ];
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn is_subset(self, other: Self) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// How many segments are in the pattern?
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, segment: char) -> bool {
+        Self::try_from(segment).is_ok_and(|s| s.is_subset(self))
+    }
+
+    /// The segments in the pattern, in alphabetical order.
+    pub fn segments(self) -> impl Iterator<Item = char> {
+        ('a'..='g').filter(move |&s| self.contains(s))
+    }
+}
+
+impl TryFrom<char> for Pattern {
+    type Error = char;
+
+    /// The pattern with just this one segment, or the character back if it isn't a segment.
+    fn try_from(segment: char) -> Result<Self, Self::Error> {
+        if !is_segment(segment) {
+            return Err(segment);
+        }
+        Ok(Self(1 << (segment as u8 - b'a')))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = char;
+
+    /// The pattern with each of these segments, or the first character which isn't a segment.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars().map(Self::try_from).collect()
+    }
+}
+
+/// Every segment in any of the patterns.
+impl FromIterator<Pattern> for Pattern {
+    fn from_iter<I: IntoIterator<Item = Pattern>>(patterns: I) -> Self {
+        patterns.into_iter().fold(Self::default(), Self::union)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.segments().try_for_each(|s| write!(f, "{}", s))
+    }
+}
 
 fn is_segment(c: char) -> bool {
     ('a'..='g').contains(&c)
 }
 
 fn to_segs(s: &str) -> Result<Pattern, &'static str> {
-    if s.is_empty() {
+    let pattern: Pattern = s.parse().map_err(|_| "not a segment")?;
+    if pattern.is_empty() {
         return Err("cannot have empty set of segments");
     }
-    Ok(s.chars().collect())
+    Ok(pattern)
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub struct DisplayPanel {
-    /// The ten different signal patterns this display uses, one for each digit.
-    pub signal_patterns: [Pattern; 10],
-    /// The signal patterns this display is currently using to show a 4-digit number.
-    pub output_value: [Pattern; 4],
+    /// The different signal patterns this display uses, one for each digit.
+    pub signal_patterns: Vec<Pattern>,
+    /// The signal patterns this display is currently using to show a number, one per digit.
+    pub output_value: Vec<Pattern>,
 }
 
 impl DisplayPanel {
     fn parse(input: &str) -> IResult<&str, Self> {
         let (input, (signal_patterns, output_value)) =
             separated_pair(signal_patterns_parser, tag(" | "), signal_patterns_parser)(input)?;
-        let signal_patterns = signal_patterns.try_into().unwrap();
-        let output_value = output_value.try_into().unwrap();
         Ok((
             input,
             Self {
@@ -47,6 +126,25 @@ impl DisplayPanel {
     }
 }
 
+/// Write the panel as a line of the puzzle input.
+impl fmt::Display for DisplayPanel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |patterns: &[Pattern]| {
+            patterns
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        write!(
+            f,
+            "{} | {}",
+            join(&self.signal_patterns),
+            join(&self.output_value)
+        )
+    }
+}
+
 /// Parse a series of patterns.
 fn signal_patterns_parser(input: &str) -> IResult<&str, Vec<Pattern>> {
     separated_list1(tag(" "), segments_parser)(input)
@@ -61,25 +159,65 @@ fn segments_parser(input: &str) -> IResult<&str, Pattern> {
 mod tests {
     use super::*;
 
+    fn pattern(segments: &str) -> Pattern {
+        segments.parse().unwrap()
+    }
+
     #[test]
     fn parse_segments() {
         let actual = segments_parser("ab").unwrap().1;
-        assert_eq!(actual, BTreeSet::from(['a', 'b']))
+        assert_eq!(actual, pattern("ab"))
+    }
+
+    #[test]
+    fn pattern_operations() {
+        let (p, q) = (pattern("abd"), pattern("bdeg"));
+        assert_eq!(p.union(q), pattern("abdeg"));
+        assert_eq!(p.intersection(q), pattern("bd"));
+        assert!(pattern("bd").is_subset(q));
+        assert!(!p.is_subset(q));
+        assert_eq!((p.len(), q.len()), (3, 4));
+        assert!(Pattern::default().is_empty());
+        assert_eq!(q.segments().collect::<String>(), "bdeg");
+        assert!(q.contains('g') && !q.contains('a') && !q.contains('h'));
+    }
+
+    #[test]
+    fn not_segments() {
+        assert_eq!(Pattern::try_from('h'), Err('h'));
+        assert_eq!("abAc".parse::<Pattern>(), Err('A'));
+        assert_eq!(Pattern::try_from('c'), Ok(Pattern::SEGMENTS[2]));
     }
 
     #[test]
     fn parse_signal_patterns() {
         let actual = signal_patterns_parser("ab def ").unwrap().1;
-        assert_eq!(
-            actual,
-            vec![BTreeSet::from(['a', 'b']), BTreeSet::from(['d', 'e', 'f']),]
-        )
+        assert_eq!(actual, vec![pattern("ab"), pattern("def")])
     }
 
     #[test]
     fn parse_line() {
         let actual = DisplayPanel::parse(include_str!("tiny.txt")).unwrap().1;
-        assert_eq!(actual.signal_patterns[9], BTreeSet::from(['a', 'b']));
+        assert_eq!(actual.signal_patterns[9], pattern("ab"));
+    }
+
+    #[test]
+    fn parse_any_length() {
+        let actual = DisplayPanel::parse("cf acf | acf cf cf acf cf acf")
+            .unwrap()
+            .1;
+        assert_eq!(actual.signal_patterns.len(), 2);
+        assert_eq!(actual.output_value.len(), 6);
+    }
+
+    #[test]
+    fn write_line() {
+        let panel = DisplayPanel::parse(include_str!("tiny.txt")).unwrap().1;
+        // The segments in each pattern come out in order.
+        let expected = "abcdefg bcdef acdfg abcdf abd abcdef bcdefg abef abcdeg ab \
+                        | bcdef abcdf bcdef abcdf";
+        assert_eq!(panel.to_string(), expected);
+        assert_eq!(DisplayPanel::parse(expected).unwrap().1, panel);
     }
 
     #[test]